@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+use usls::{export, models::YOLO, DataLoader, Options};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // build model
+    let options = Options::default()
+        .with_model("yolov8m-dyn.onnx")?
+        .with_i00((1, 1, 4).into())
+        .with_i02((224, 640, 800).into())
+        .with_i03((224, 640, 800).into())
+        .with_confs(&[0.25]);
+    let mut model = YOLO::new(options)?;
+
+    // image
+    let path = PathBuf::from("./assets/bus.jpg");
+    let xs = vec![DataLoader::try_read(&path)?];
+
+    // dump each stage
+    let saveout = PathBuf::from("runs").join("YOLOv8-Golden");
+    std::fs::create_dir_all(&saveout)?;
+
+    // preprocess: letterboxed & normalized input
+    let (xs_, transforms) = model.preprocess(&xs)?;
+    export::npy(&xs_, std::fs::File::create(saveout.join("input.npy"))?)?;
+    println!("Transforms: {transforms:?}");
+
+    // inference: raw outputs
+    let ys_ = model.inference(xs_)?;
+    for (i, y) in ys_.iter().enumerate() {
+        export::npy(
+            y,
+            std::fs::File::create(saveout.join(format!("output{i}.npy")))?,
+        )?;
+    }
+
+    // postprocess: decoded detections, before any annotator
+    let ys = model.postprocess(ys_, &xs, &transforms)?;
+    export::csv(
+        &ys,
+        &[path],
+        std::fs::File::create(saveout.join("detections.csv"))?,
+        true,
+    )?;
+    println!("{}", ys[0].to_table());
+    println!("Dumped to: {saveout:?}");
+
+    Ok(())
+}
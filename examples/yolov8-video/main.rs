@@ -0,0 +1,40 @@
+use usls::{coco, models::YOLO, Annotator, Options, VideoReader, VideoWriter};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // source & saveout
+    let args = std::env::args().collect::<Vec<_>>();
+    let source = args
+        .get(1)
+        .map(|x| x.as_str())
+        .unwrap_or("./assets/video.mp4");
+    let saveout = args
+        .get(2)
+        .map(|x| x.as_str())
+        .unwrap_or("runs/YOLOv8-Video/out.mp4");
+
+    // build model
+    let options = Options::default()
+        .with_model("yolov8m-dyn.onnx")?
+        .with_i00((1, 1, 4).into())
+        .with_i02((224, 640, 800).into())
+        .with_i03((224, 640, 800).into())
+        .with_confs(&[0.4, 0.15])
+        .with_names2(&coco::KEYPOINTS_NAMES_17);
+    let mut model = YOLO::new(options)?;
+
+    // build annotator
+    let annotator = Annotator::default().with_skeletons(&coco::SKELETONS_16);
+
+    // decode, run & encode, frame by frame
+    let mut reader = VideoReader::open(source)?;
+    let mut writer = VideoWriter::create(saveout, reader.width(), reader.height(), reader.fps())?;
+    for x in reader.by_ref() {
+        let ys = model.run(std::slice::from_ref(&x))?;
+        writer.write(&annotator.plot(&x, &ys[0]))?;
+    }
+    reader.finish()?;
+    writer.finish()?;
+    println!("Annotated video saved to: {saveout}");
+
+    Ok(())
+}
@@ -0,0 +1,7 @@
+/// Activation applied to raw classification outputs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Activation {
+    #[default]
+    None, // already normalized in-graph, e.g. ultralytics exports
+    Softmax, // raw logits
+}
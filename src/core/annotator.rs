@@ -1,11 +1,13 @@
 use crate::{
-    auto_load, colormap256, string_now, Bbox, Keypoint, Mask, Mbr, Polygon, Prob, CHECK_MARK,
-    CROSS_MARK, Y,
+    auto_load, colormap256, string_now, Bbox, ChannelOrder, Keypoint, Mask, Mbr, Polygon, Prob,
+    CHECK_MARK, CROSS_MARK, Y,
 };
 use ab_glyph::{FontVec, PxScale};
 use anyhow::Result;
 use image::{DynamicImage, GenericImage, Rgba, RgbaImage};
 use imageproc::map::map_colors;
+use std::collections::HashMap;
+use std::path::PathBuf;
 
 /// Annotator for struct `Y`
 #[derive(Debug)]
@@ -13,8 +15,13 @@ pub struct Annotator {
     font: FontVec,
     _scale: f32, // Cope with ab_glyph & imageproc=0.24.0
     scale_dy: f32,
+    line_thickness: usize,
     saveout: Option<String>,
+    save_txt: bool,
+    save_txt_conf: bool,
+    print_table: bool,
     decimal_places: usize,
+    output_channel_order: ChannelOrder,
 
     // About mbrs
     without_mbrs: bool,
@@ -29,6 +36,8 @@ pub struct Annotator {
     without_bboxes_name: bool,
     without_bboxes_text_bg: bool,
     bboxes_text_color: Rgba<u8>,
+    bboxes_corner_radius: f32,
+    bboxes_antialias: bool,
 
     // About keypoints
     without_keypoints: bool,
@@ -43,11 +52,14 @@ pub struct Annotator {
     // About polygons
     without_polygons: bool,
     without_contours: bool,
+    without_polygons_fill: bool,
+    contours_class_color: bool,
     with_polygons_conf: bool,
     with_polygons_name: bool,
     with_polygons_text_bg: bool,
     polygons_text_color: Rgba<u8>,
     polygons_alpha: u8,
+    mask_alpha: Option<f32>, // None: masks tiled next to the image
     contours_color: Rgba<u8>,
 
     // About masks
@@ -56,6 +68,13 @@ pub struct Annotator {
 
     // About probs
     probs_topk: usize,
+
+    // About summary
+    with_summary_banner: bool,
+
+    // class name or id -> color
+    class_colors: HashMap<String, (u8, u8, u8, u8)>,
+    palette: Option<Vec<(u8, u8, u8, u8)>>, // colors by class id, None: the built-in one
 }
 
 impl Default for Annotator {
@@ -64,14 +83,22 @@ impl Default for Annotator {
             font: Self::load_font(None).unwrap(),
             _scale: 6.666667,
             scale_dy: 28.,
+            line_thickness: 1,
             polygons_alpha: 179,
+            mask_alpha: None,
             saveout: None,
+            save_txt: false,
+            save_txt_conf: false,
+            print_table: false,
             decimal_places: 4,
+            output_channel_order: ChannelOrder::Rgb,
             without_bboxes: false,
             without_bboxes_conf: false,
             without_bboxes_name: false,
             bboxes_text_color: Rgba([0, 0, 0, 255]),
             without_bboxes_text_bg: false,
+            bboxes_corner_radius: 0.,
+            bboxes_antialias: false,
             without_mbrs: false,
             without_mbrs_conf: false,
             without_mbrs_name: false,
@@ -87,12 +114,17 @@ impl Default for Annotator {
             keypoints_text_color: Rgba([0, 0, 0, 255]),
             without_polygons: false,
             without_contours: false,
+            without_polygons_fill: false,
+            contours_class_color: false,
             contours_color: Rgba([255, 255, 255, 255]),
             with_polygons_name: false,
             with_polygons_conf: false,
             with_polygons_text_bg: false,
             polygons_text_color: Rgba([255, 255, 255, 255]),
             probs_topk: 5usize,
+            with_summary_banner: false,
+            class_colors: HashMap::new(),
+            palette: None,
             without_masks: false,
             colormap: None,
         }
@@ -105,6 +137,27 @@ impl Annotator {
         self
     }
 
+    /// Same as `with_decimal_places`
+    pub fn with_conf_decimals(self, x: usize) -> Self {
+        self.with_decimal_places(x)
+    }
+
+    /// Class names in labels of bboxes, mbrs & polygons; no text at all along with `with_conf(false)`
+    pub fn with_labels(mut self, x: bool) -> Self {
+        self.without_bboxes_name = !x;
+        self.without_mbrs_name = !x;
+        self.with_polygons_name = x;
+        self
+    }
+
+    /// Confidences in labels of bboxes, mbrs & polygons
+    pub fn with_conf(mut self, x: bool) -> Self {
+        self.without_bboxes_conf = !x;
+        self.without_mbrs_conf = !x;
+        self.with_polygons_conf = x;
+        self
+    }
+
     /// Plotting BBOXes or not
     pub fn without_bboxes(mut self, x: bool) -> Self {
         self.without_bboxes = x;
@@ -136,6 +189,56 @@ impl Annotator {
         self
     }
 
+    /// Class colors from a JSON file: `{"person": [255, 0, 0], "2": [0, 255, 0, 255]}`, keys are names or ids
+    pub fn with_colors_file(mut self, path: &str) -> Result<Self> {
+        let s = std::fs::read_to_string(path)
+            .map_err(|err| anyhow::anyhow!("Failed to read colors file {path:?}: {err}"))?;
+        let map: HashMap<String, Vec<u8>> = serde_json::from_str(&s)
+            .map_err(|err| anyhow::anyhow!("Failed to parse colors file {path:?}: {err}"))?;
+        for (k, v) in map.into_iter() {
+            let color = match v[..] {
+                [r, g, b] => (r, g, b, 255),
+                [r, g, b, a] => (r, g, b, a),
+                _ => anyhow::bail!("Color of {k:?} should be [r, g, b] or [r, g, b, a], got {v:?}"),
+            };
+            self.class_colors.insert(k, color);
+        }
+        Ok(self)
+    }
+
+    /// Colors by class id (modulo its length) instead of the built-in 20, `with_colors_file` ones first
+    pub fn with_palette(mut self, x: &[(u8, u8, u8, u8)]) -> Self {
+        if !x.is_empty() {
+            self.palette = Some(x.to_vec());
+        }
+        self
+    }
+
+    /// Palette of `n` distinct colors, spread over hues, the same for the same `n`
+    pub fn with_palette_size(mut self, n: usize) -> Self {
+        self.palette = Some(Self::generate_palette(n.max(1)));
+        self
+    }
+
+    /// Banner with total & per-class detection counts on top of the image
+    pub fn with_summary_banner(mut self, x: bool) -> Self {
+        self.with_summary_banner = x;
+        self
+    }
+
+    /// Rounded corners of BBOXes, 0 for sharp ones
+    pub fn with_corner_radius(mut self, x: f32) -> Self {
+        self.bboxes_corner_radius = x;
+        self
+    }
+
+    /// Anti-aliased BBOXes edges of any thickness. Only slanted & curved edges change, so this
+    /// shows with `with_corner_radius`, sharp axis-aligned boxes look the same either way.
+    pub fn with_antialias(mut self, x: bool) -> Self {
+        self.bboxes_antialias = x;
+        self
+    }
+
     pub fn without_keypoints(mut self, x: bool) -> Self {
         self.without_keypoints = x;
         self
@@ -267,11 +370,30 @@ impl Annotator {
         self
     }
 
+    /// Blend masks over the image at opacity `x` in [0, 1], instead of tiling them next to it.
+    /// Polygon fills keep their own opacity, see `with_polygons_alpha`.
+    pub fn with_mask_alpha(mut self, x: f32) -> Self {
+        self.mask_alpha = Some(x.clamp(0., 1.));
+        self
+    }
+
     pub fn with_polygons_text_bg_alpha(mut self, x: u8) -> Self {
         self.polygons_text_color.0[3] = x;
         self
     }
 
+    /// Contours only, the image inside polygons stays visible
+    pub fn without_polygons_fill(mut self, x: bool) -> Self {
+        self.without_polygons_fill = x;
+        self
+    }
+
+    /// Contours in class colors instead of `contours_color`, thickness from `with_line_thickness`
+    pub fn with_contours_class_color(mut self, x: bool) -> Self {
+        self.contours_class_color = x;
+        self
+    }
+
     pub fn with_contours_color(mut self, rgba: [u8; 4]) -> Self {
         self.contours_color = Rgba(rgba);
         self
@@ -287,6 +409,44 @@ impl Annotator {
         self
     }
 
+    /// Print results as a table (`Y::to_table`) when annotating
+    pub fn with_print_table(mut self, x: bool) -> Self {
+        self.print_table = x;
+        self
+    }
+
+    /// Also save YOLO txt labels next to the annotated images, see `Y::to_yolo_txt`
+    pub fn with_save_txt(mut self, x: bool) -> Self {
+        self.save_txt = x;
+        self
+    }
+
+    /// Append confidences to the saved txt labels
+    pub fn with_save_txt_conf(mut self, x: bool) -> Self {
+        self.save_txt_conf = x;
+        self
+    }
+
+    /// Channel order of plotted & saved images, RGB by default
+    pub fn with_output_channel_order(mut self, x: ChannelOrder) -> Self {
+        self.output_channel_order = x;
+        self
+    }
+
+    /// Width in pixels of bbox, mbr & skeleton lines, 1 by default
+    pub fn with_line_thickness(mut self, x: usize) -> Self {
+        self.line_thickness = x.max(1);
+        self
+    }
+
+    /// Height in pixels of label texts, 28 by default
+    pub fn with_font_size(mut self, x: f32) -> Self {
+        if x > 0. {
+            self.scale_dy = x;
+        }
+        self
+    }
+
     pub fn with_font(mut self, path: &str) -> Self {
         self.font = Self::load_font(Some(path)).unwrap();
         self
@@ -294,11 +454,16 @@ impl Annotator {
 
     /// Save annotated images to `runs` folder
     pub fn save(&self, image: &RgbaImage, saveout: &str) {
+        self.save_as(image, saveout, &string_now("-"))
+    }
+
+    /// Save annotated images to `runs` folder as `{name}.png`
+    pub fn save_as(&self, image: &RgbaImage, saveout: &str, name: &str) {
         let mut saveout = std::path::PathBuf::from("runs").join(saveout);
         if !saveout.exists() {
             std::fs::create_dir_all(&saveout).unwrap();
         }
-        saveout.push(string_now("-"));
+        saveout.push(name);
         let saveout = format!("{}.png", saveout.to_str().unwrap());
         match image.save(&saveout) {
             Err(err) => println!("{} Saving failed: {:?}", CROSS_MARK, err),
@@ -306,68 +471,183 @@ impl Annotator {
         }
     }
 
+    /// Save YOLO txt labels of `y` to `runs` folder as `{name}.txt`
+    pub fn save_txt_as(&self, y: &Y, img: &DynamicImage, saveout: &str, name: &str) {
+        let saveout = std::path::PathBuf::from("runs").join(saveout);
+        if !saveout.exists() {
+            std::fs::create_dir_all(&saveout).unwrap();
+        }
+        let saveout = saveout.join(format!("{name}.txt"));
+        let txt = match self.save_txt_conf {
+            true => y.to_yolo_txt_with_conf(img.width(), img.height()),
+            false => y.to_yolo_txt(img.width(), img.height()),
+        };
+        if let Err(err) = std::fs::write(&saveout, txt) {
+            println!("{} Saving failed: {:?}", CROSS_MARK, err);
+        }
+    }
+
     /// Annotate images
     pub fn annotate(&self, imgs: &[DynamicImage], ys: &[Y]) {
         for (img, y) in imgs.iter().zip(ys.iter()) {
-            let mut img_rgba = img.to_rgba8();
+            let img_rgba = self.plot(img, y);
 
-            // polygons
-            if !self.without_polygons {
-                if let Some(xs) = &y.polygons() {
-                    self.plot_polygons(&mut img_rgba, xs)
-                }
+            // print
+            if self.print_table {
+                println!("{}", y.to_table());
             }
 
-            // bboxes
-            if !self.without_bboxes {
-                if let Some(xs) = &y.bboxes() {
-                    self.plot_bboxes(&mut img_rgba, xs)
+            // save
+            if let Some(saveout) = &self.saveout {
+                let name = string_now("-");
+                self.save_as(&img_rgba, saveout, &name);
+                if self.save_txt {
+                    self.save_txt_as(y, img, saveout, &name);
                 }
             }
+        }
+    }
 
-            // mbrs
-            if !self.without_mbrs {
-                if let Some(xs) = &y.mbrs() {
-                    self.plot_mbrs(&mut img_rgba, xs)
+    /// Annotate images, saved with the file stems of their source `paths`
+    pub fn annotate_with_paths(&self, imgs: &[DynamicImage], ys: &[Y], paths: &[PathBuf]) {
+        for ((img, y), path) in imgs.iter().zip(ys.iter()).zip(paths.iter()) {
+            let img_rgba = self.plot(img, y);
+            if self.print_table {
+                println!("{:?}\n{}", path, y.to_table());
+            }
+            if let Some(saveout) = &self.saveout {
+                let name = path
+                    .file_stem()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or_else(|| string_now("-"));
+                self.save_as(&img_rgba, saveout, &name);
+                if self.save_txt {
+                    self.save_txt_as(y, img, saveout, &name);
                 }
             }
+        }
+    }
 
-            // keypoints
-            if !self.without_keypoints {
-                if let Some(xs) = &y.keypoints() {
-                    self.plot_keypoints(&mut img_rgba, xs)
-                }
+    /// Same as `plot`, as a `DynamicImage`, e.g. for serving without touching disk
+    pub fn plot_to_image(&self, base: &DynamicImage, y: &Y) -> DynamicImage {
+        DynamicImage::from(self.plot(base, y))
+    }
+
+    /// Same as `plot`, encoded as PNG bytes
+    pub fn plot_to_png(&self, base: &DynamicImage, y: &Y) -> Result<Vec<u8>> {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        self.plot(base, y)
+            .write_to(&mut buf, image::ImageFormat::Png)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Annotated image of one `Y`, in the output channel order
+    pub fn plot(&self, img: &DynamicImage, y: &Y) -> RgbaImage {
+        let mut img_rgba = img.to_rgba8();
+
+        // masks, blended under everything else
+        if let (false, Some(alpha), Some(xs)) = (self.without_masks, self.mask_alpha, y.masks()) {
+            for mask in xs.iter() {
+                let color = self.class_color(mask.id(), mask.name());
+                Self::blend_mask(
+                    &mut img_rgba,
+                    mask.mask(),
+                    [color.0, color.1, color.2],
+                    self.colormap.as_ref(),
+                    alpha,
+                );
             }
+        }
 
-            // probs
-            if let Some(xs) = &y.probs() {
-                self.plot_probs(&mut img_rgba, xs)
+        // polygons
+        if !self.without_polygons {
+            if let Some(xs) = &y.polygons() {
+                self.plot_polygons(&mut img_rgba, xs)
             }
+        }
 
-            // masks
-            if !self.without_masks {
-                if let Some(xs) = &y.masks() {
-                    self.plot_masks(&mut img_rgba, xs)
-                }
+        // bboxes
+        if !self.without_bboxes {
+            if let Some(xs) = &y.bboxes() {
+                self.plot_bboxes(&mut img_rgba, xs)
             }
+        }
 
-            // save
-            if let Some(saveout) = &self.saveout {
-                self.save(&img_rgba, saveout);
+        // mbrs
+        if !self.without_mbrs {
+            if let Some(xs) = &y.mbrs() {
+                self.plot_mbrs(&mut img_rgba, xs)
             }
         }
+
+        // keypoints
+        if !self.without_keypoints {
+            if let Some(xs) = &y.keypoints() {
+                self.plot_keypoints(&mut img_rgba, xs)
+            }
+        }
+
+        // probs
+        if let Some(xs) = &y.probs() {
+            self.plot_probs(&mut img_rgba, xs)
+        }
+
+        // masks
+        if !self.without_masks && self.mask_alpha.is_none() {
+            if let Some(xs) = &y.masks() {
+                self.plot_masks(&mut img_rgba, xs)
+            }
+        }
+
+        // summary
+        if self.with_summary_banner {
+            self.plot_summary_banner(&mut img_rgba, y);
+        }
+
+        self.output_channel_order.apply(&mut img_rgba);
+        img_rgba
     }
 
     /// Plot bounding bboxes and labels
     pub fn plot_bboxes(&self, img: &mut RgbaImage, bboxes: &[Bbox]) {
         for bbox in bboxes.iter() {
             // bbox
-            imageproc::drawing::draw_hollow_rect_mut(
-                img,
-                imageproc::rect::Rect::at(bbox.xmin().round() as i32, bbox.ymin().round() as i32)
-                    .of_size(bbox.width().round() as u32, bbox.height().round() as u32),
-                image::Rgba(self.get_color(bbox.id() as usize).into()),
-            );
+            let color = image::Rgba(self.class_color(bbox.id(), bbox.name()).into());
+            if self.bboxes_corner_radius <= 0. && !self.bboxes_antialias {
+                // grow both inwards & outwards
+                let t = self.line_thickness as i32;
+                for o in -(t - 1) / 2..=t / 2 {
+                    let (w, h) = (bbox.width().round() as i32, bbox.height().round() as i32);
+                    if w + 2 * o <= 0 || h + 2 * o <= 0 {
+                        continue;
+                    }
+                    imageproc::drawing::draw_hollow_rect_mut(
+                        img,
+                        imageproc::rect::Rect::at(
+                            bbox.xmin().round() as i32 - o,
+                            bbox.ymin().round() as i32 - o,
+                        )
+                        .of_size((w + 2 * o) as u32, (h + 2 * o) as u32),
+                        color,
+                    );
+                }
+            } else {
+                let outline = Self::rounded_rect_outline(
+                    bbox.xmin(),
+                    bbox.ymin(),
+                    bbox.xmax(),
+                    bbox.ymax(),
+                    self.bboxes_corner_radius,
+                );
+                for (i, &start) in outline.iter().enumerate() {
+                    let end = outline[(i + 1) % outline.len()];
+                    if self.bboxes_antialias {
+                        self.draw_line_antialiased(img, start, end, color);
+                    } else {
+                        self.draw_line(img, start, end, color);
+                    }
+                }
+            }
 
             // label
             if !self.without_bboxes_name || !self.without_bboxes_conf {
@@ -380,8 +660,8 @@ impl Annotator {
                     img,
                     &label,
                     bbox.xmin(),
-                    bbox.ymin(),
-                    image::Rgba(self.get_color(bbox.id() as usize).into()),
+                    bbox.ymin() - (self.line_thickness / 2) as f32, // above the line
+                    image::Rgba(self.class_color(bbox.id(), bbox.name()).into()),
                     self.bboxes_text_color,
                     self.without_bboxes_text_bg,
                 );
@@ -396,11 +676,11 @@ impl Annotator {
             for i in 0..mbr.vertices().len() {
                 let p1 = mbr.vertices()[i];
                 let p2 = mbr.vertices()[(i + 1) % mbr.vertices().len()];
-                imageproc::drawing::draw_line_segment_mut(
+                self.draw_line(
                     img,
                     (p1.x.round() as f32, p1.y.round() as f32),
                     (p2.x.round() as f32, p2.y.round() as f32),
-                    image::Rgba(self.get_color(mbr.id() as usize).into()),
+                    image::Rgba(self.class_color(mbr.id(), mbr.name()).into()),
                 );
             }
 
@@ -416,7 +696,7 @@ impl Annotator {
                     &label,
                     mbr.top().x as f32,
                     mbr.top().y as f32,
-                    image::Rgba(self.get_color(mbr.id() as usize).into()),
+                    image::Rgba(self.class_color(mbr.id(), mbr.name()).into()),
                     self.mbrs_text_color,
                     self.without_mbrs_text_bg,
                 );
@@ -426,10 +706,8 @@ impl Annotator {
 
     /// Plot polygons(hollow & filled) and labels
     pub fn plot_polygons(&self, img: &mut RgbaImage, polygons: &[Polygon]) {
-        let mut convas = img.clone();
-        for polygon in polygons.iter() {
-            // filled
-            let polygon_i32 = polygon
+        let points = |polygon: &Polygon| {
+            polygon
                 .polygon()
                 .exterior()
                 .points()
@@ -438,29 +716,42 @@ impl Annotator {
                 } else {
                     polygon.count()
                 })
-                .map(|p| imageproc::point::Point::new(p.x() as i32, p.y() as i32))
-                .collect::<Vec<_>>();
-            let mut color_ = self.get_color(polygon.id() as usize);
-            color_.3 = self.polygons_alpha;
-            imageproc::drawing::draw_polygon_mut(&mut convas, &polygon_i32, Rgba(color_.into()));
-
-            // contour
-            if !self.without_contours {
-                let polygon_f32 = polygon
-                    .polygon()
-                    .exterior()
-                    .points()
-                    .take(if polygon.is_closed() {
-                        polygon.count() - 1
-                    } else {
-                        polygon.count()
-                    })
-                    .map(|p| imageproc::point::Point::new(p.x() as f32, p.y() as f32))
+                .map(|p| (p.x() as f32, p.y() as f32))
+                .collect::<Vec<_>>()
+        };
+
+        // filled
+        if !self.without_polygons_fill {
+            let mut convas = img.clone();
+            for polygon in polygons.iter() {
+                let polygon_i32 = points(polygon)
+                    .into_iter()
+                    .map(|(x, y)| imageproc::point::Point::new(x as i32, y as i32))
                     .collect::<Vec<_>>();
-                imageproc::drawing::draw_hollow_polygon_mut(img, &polygon_f32, self.contours_color);
+                let mut color_ = self.class_color(polygon.id(), polygon.name());
+                color_.3 = self.polygons_alpha;
+                imageproc::drawing::draw_polygon_mut(
+                    &mut convas,
+                    &polygon_i32,
+                    Rgba(color_.into()),
+                );
+            }
+            image::imageops::overlay(img, &convas, 0, 0);
+        }
+
+        // contours, above fills
+        if !self.without_contours {
+            for polygon in polygons.iter() {
+                let color = match self.contours_class_color {
+                    true => Rgba(self.class_color(polygon.id(), polygon.name()).into()),
+                    false => self.contours_color,
+                };
+                let points = points(polygon);
+                for (i, &start) in points.iter().enumerate() {
+                    self.draw_line(img, start, points[(i + 1) % points.len()], color);
+                }
             }
         }
-        image::imageops::overlay(img, &convas, 0, 0);
 
         // labels on top
         if self.with_polygons_name || self.with_polygons_conf {
@@ -476,7 +767,7 @@ impl Annotator {
                         &label,
                         x,
                         y,
-                        image::Rgba(self.get_color(polygon.id() as usize).into()),
+                        image::Rgba(self.class_color(polygon.id(), polygon.name()).into()),
                         self.polygons_text_color,
                         !self.with_polygons_text_bg,
                     );
@@ -489,7 +780,7 @@ impl Annotator {
     pub fn plot_keypoints(&self, img: &mut RgbaImage, keypoints: &[Vec<Keypoint>]) {
         for kpts in keypoints.iter() {
             for (i, kpt) in kpts.iter().enumerate() {
-                if kpt.confidence() == 0.0 {
+                if !kpt.visible() {
                     continue;
                 }
 
@@ -529,10 +820,10 @@ impl Annotator {
                 for &(i, ii) in skeletons.iter() {
                     let kpt1 = &kpts[i];
                     let kpt2 = &kpts[ii];
-                    if kpt1.confidence() == 0.0 || kpt2.confidence() == 0.0 {
+                    if !kpt1.visible() || !kpt2.visible() {
                         continue;
                     }
-                    imageproc::drawing::draw_line_segment_mut(
+                    self.draw_line(
                         img,
                         (kpt1.x(), kpt1.y()),
                         (kpt2.x(), kpt2.y()),
@@ -613,10 +904,42 @@ impl Annotator {
         *img = convas.into_rgba8();
     }
 
+    /// Blend `mask` over `img` at opacity `alpha`: through `colormap` everywhere if any,
+    /// otherwise in `color` weighted by mask values
+    fn blend_mask(
+        img: &mut RgbaImage,
+        mask: &DynamicImage,
+        color: [u8; 3],
+        colormap: Option<&[[u8; 3]; 256]>,
+        alpha: f32,
+    ) {
+        let mut mask = mask.to_luma8();
+        if mask.dimensions() != img.dimensions() {
+            mask = image::imageops::resize(
+                &mask,
+                img.width(),
+                img.height(),
+                image::imageops::FilterType::Triangle,
+            );
+        }
+        for (p, m) in img.pixels_mut().zip(mask.pixels()) {
+            let (c, w) = match colormap {
+                Some(colormap) => (colormap[m.0[0] as usize], alpha),
+                None => (color, alpha * m.0[0] as f32 / 255.),
+            };
+            for (x, &c) in p.0.iter_mut().zip(c.iter()) {
+                *x = (*x as f32 * (1. - w) + c as f32 * w).round() as u8;
+            }
+        }
+    }
+
     /// Plot probs
     pub fn plot_probs(&self, img: &mut RgbaImage, probs: &Prob) {
         let (x, mut y) = (img.width() as i32 / 20, img.height() as i32 / 20);
-        for k in probs.topk(self.probs_topk).iter() {
+        let ks = probs
+            .labels()
+            .unwrap_or_else(|| probs.topk(self.probs_topk));
+        for k in ks.iter() {
             let legend = format!("{}: {:.4}", k.2.as_ref().unwrap_or(&k.0.to_string()), k.1);
             let scale = PxScale::from(self.scale_dy);
             let (text_w, text_h) = imageproc::drawing::text_size(scale, &self.font, &legend);
@@ -625,7 +948,7 @@ impl Annotator {
             imageproc::drawing::draw_filled_rect_mut(
                 img,
                 imageproc::rect::Rect::at(x, y).of_size(text_w, text_h),
-                image::Rgba(self.get_color(k.0).into()),
+                image::Rgba(self.class_color(k.0 as isize, k.2.as_ref()).into()),
             );
             imageproc::drawing::draw_text_mut(
                 img,
@@ -639,6 +962,122 @@ impl Annotator {
         }
     }
 
+    /// Plot detection counts on a semi-transparent strip
+    pub fn plot_summary_banner(&self, img: &mut RgbaImage, y: &Y) {
+        let histogram = y.class_histogram();
+        let total: usize = histogram.iter().map(|(_, n)| n).sum();
+        let mut legend = format!("Total: {total}");
+        if !histogram.is_empty() {
+            let classes = histogram
+                .iter()
+                .map(|(class, n)| format!("{class}: {n}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            legend = format!("{legend} | {classes}");
+        }
+
+        // strip
+        let scale = PxScale::from(self.scale_dy);
+        let (_, text_h) = imageproc::drawing::text_size(scale, &self.font, &legend);
+        let pad = 4u32;
+        let strip_h = (text_h + text_h / 3 + 2 * pad).min(img.height());
+        for y in 0..strip_h {
+            for x in 0..img.width() {
+                let p = img.get_pixel_mut(x, y);
+                for c in p.0.iter_mut().take(3) {
+                    *c /= 2;
+                }
+            }
+        }
+
+        // text
+        imageproc::drawing::draw_text_mut(
+            img,
+            Rgba([255, 255, 255, 255]),
+            pad as i32,
+            pad as i32 - (self.scale_dy / self._scale).floor() as i32 + 2,
+            scale,
+            &self.font,
+            &legend,
+        );
+    }
+
+    /// Vertices of a rectangle with rounded corners, clockwise from the top-left arc
+    fn rounded_rect_outline(x1: f32, y1: f32, x2: f32, y2: f32, r: f32) -> Vec<(f32, f32)> {
+        let r = r.max(0.).min((x2 - x1).min(y2 - y1) / 2.);
+        let n = if r > 0. { (r as usize / 2).max(4) } else { 1 };
+        let mut vertices = Vec::new();
+        for (cx, cy, start) in [
+            (x1 + r, y1 + r, 1.0f32),
+            (x2 - r, y1 + r, 1.5),
+            (x2 - r, y2 - r, 0.),
+            (x1 + r, y2 - r, 0.5),
+        ] {
+            for i in 0..=n {
+                let theta = (start + 0.5 * i as f32 / n as f32) * std::f32::consts::PI;
+                vertices.push((cx + r * theta.cos(), cy + r * theta.sin()));
+            }
+        }
+        vertices
+    }
+
+    /// Line segment of `line_thickness`, as parallel segments half a pixel apart
+    fn draw_line(&self, img: &mut RgbaImage, start: (f32, f32), end: (f32, f32), color: Rgba<u8>) {
+        let t = self.line_thickness as f32;
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if t <= 1. || len == 0. {
+            imageproc::drawing::draw_line_segment_mut(img, start, end, color);
+            return;
+        }
+        let (nx, ny) = (-dy / len, dx / len);
+        for k in 0..(2. * t - 1.) as usize {
+            let o = k as f32 / 2. - (t - 1.) / 2.;
+            imageproc::drawing::draw_line_segment_mut(
+                img,
+                (start.0 + nx * o, start.1 + ny * o),
+                (end.0 + nx * o, end.1 + ny * o),
+                color,
+            );
+        }
+    }
+
+    /// Same as `draw_line`, with anti-aliased outer edges
+    fn draw_line_antialiased(
+        &self,
+        img: &mut RgbaImage,
+        start: (f32, f32),
+        end: (f32, f32),
+        color: Rgba<u8>,
+    ) {
+        let aa = |img: &mut RgbaImage, start: (f32, f32), end: (f32, f32)| {
+            imageproc::drawing::draw_antialiased_line_segment_mut(
+                img,
+                (start.0.round() as i32, start.1.round() as i32),
+                (end.0.round() as i32, end.1.round() as i32),
+                color,
+                imageproc::pixelops::interpolate,
+            )
+        };
+        let t = self.line_thickness as f32;
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if t <= 1. || len == 0. {
+            aa(img, start, end);
+            return;
+        }
+        // solid core, then soft edges on both sides
+        self.draw_line(img, start, end, color);
+        let (nx, ny) = (-dy / len, dx / len);
+        for o in [-t / 2., t / 2.] {
+            aa(
+                img,
+                (start.0 + nx * o, start.1 + ny * o),
+                (end.0 + nx * o, end.1 + ny * o),
+            );
+        }
+    }
+
     /// Helper for putting texts
     #[allow(clippy::too_many_arguments)]
     fn put_text(
@@ -697,9 +1136,43 @@ impl Annotator {
         Ok(FontVec::try_from_vec(buffer.to_owned()).unwrap())
     }
 
+    /// Color of a class: by name then id from `with_colors_file`, or the palette by id
+    pub fn class_color(&self, id: isize, name: Option<&String>) -> (u8, u8, u8, u8) {
+        name.and_then(|x| self.class_colors.get(x))
+            .or_else(|| self.class_colors.get(&id.to_string()))
+            .copied()
+            .unwrap_or_else(|| self.get_color(id as usize))
+    }
+
     /// Pick color from pallette
     pub fn get_color(&self, n: usize) -> (u8, u8, u8, u8) {
-        Self::color_palette()[n % Self::color_palette().len()]
+        match &self.palette {
+            Some(palette) => palette[n % palette.len()],
+            None => Self::color_palette()[n % Self::color_palette().len()],
+        }
+    }
+
+    /// Evenly spaced hues at fixed saturation & value
+    fn generate_palette(n: usize) -> Vec<(u8, u8, u8, u8)> {
+        let (s, v) = (0.75f32, 0.95f32);
+        (0..n)
+            .map(|i| {
+                let h = i as f32 / n as f32 * 6.;
+                let c = v * s;
+                let x = c * (1. - (h % 2. - 1.).abs());
+                let (r, g, b) = match h as usize {
+                    0 => (c, x, 0.),
+                    1 => (x, c, 0.),
+                    2 => (0., c, x),
+                    3 => (0., x, c),
+                    4 => (x, 0., c),
+                    _ => (c, 0., x),
+                };
+                let m = v - c;
+                let u = |x: f32| ((x + m) * 255.).round() as u8;
+                (u(r), u(g), u(b), 255)
+            })
+            .collect()
     }
 
     /// Color pallette
@@ -728,3 +1201,43 @@ impl Annotator {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests_annotator {
+    use super::Annotator;
+    use image::{DynamicImage, GrayImage, Luma, Rgba, RgbaImage};
+
+    #[test]
+    fn mask_alpha() {
+        let mut mask = GrayImage::new(2, 1);
+        mask.put_pixel(1, 0, Luma([255]));
+        let mask = DynamicImage::from(mask);
+        let base = RgbaImage::from_pixel(2, 1, Rgba([0, 0, 0, 255]));
+        for (alpha, v) in [(0., 0), (0.5, 100), (1., 200)] {
+            let mut img = base.clone();
+            Annotator::blend_mask(&mut img, &mask, [200, 200, 200], None, alpha);
+            assert_eq!(img.get_pixel(0, 0).0, [0, 0, 0, 255]);
+            assert_eq!(img.get_pixel(1, 0).0, [v, v, v, 255]);
+        }
+    }
+
+    #[test]
+    fn generated_palette() {
+        let xs = Annotator::generate_palette(5);
+        assert_eq!(xs, Annotator::generate_palette(5));
+        assert_eq!(xs[0], (242, 61, 61, 255));
+        for (i, a) in xs.iter().enumerate() {
+            for b in xs[i + 1..].iter() {
+                let d = [
+                    a.0 as i32 - b.0 as i32,
+                    a.1 as i32 - b.1 as i32,
+                    a.2 as i32 - b.2 as i32,
+                ];
+                assert!(
+                    d.iter().map(|x| x.abs()).sum::<i32>() > 100,
+                    "{a:?} vs {b:?}"
+                );
+            }
+        }
+    }
+}
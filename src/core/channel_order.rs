@@ -0,0 +1,57 @@
+use image::RgbaImage;
+use ndarray::{s, Array, IxDyn, Zip};
+
+/// Channel order of model inputs or annotated images, alpha stays last
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelOrder {
+    #[default]
+    Rgb,
+    Bgr, // OpenCV
+}
+
+impl ChannelOrder {
+    /// Reorder pixels of an RGBA image in place
+    pub fn apply(&self, img: &mut RgbaImage) {
+        if let Self::Bgr = self {
+            for p in img.pixels_mut() {
+                p.0.swap(0, 2);
+            }
+        }
+    }
+
+    /// Reorder channels of a [b, 3, h, w] RGB tensor, in place
+    pub fn apply_nchw(&self, mut xs: Array<f32, IxDyn>) -> Array<f32, IxDyn> {
+        if let Self::Bgr = self {
+            let (mut r, mut b) = xs.multi_slice_mut((s![.., 0, .., ..], s![.., 2, .., ..]));
+            Zip::from(&mut r).and(&mut b).for_each(std::mem::swap);
+        }
+        xs
+    }
+}
+
+#[cfg(test)]
+mod tests_channel_order {
+    use super::ChannelOrder;
+    use image::{Rgba, RgbaImage};
+    use ndarray::{Array, IxDyn};
+
+    #[test]
+    fn bgr() {
+        let mut img = RgbaImage::from_pixel(2, 1, Rgba([1, 2, 3, 4]));
+        ChannelOrder::Rgb.apply(&mut img);
+        assert_eq!(img.get_pixel(0, 0).0, [1, 2, 3, 4]);
+        ChannelOrder::Bgr.apply(&mut img);
+        assert_eq!(img.get_pixel(1, 0).0, [3, 2, 1, 4]);
+    }
+
+    #[test]
+    fn bgr_nchw() {
+        let xs = Array::from_shape_vec(IxDyn(&[1, 3, 1, 2]), vec![1., 1., 2., 2., 3., 3.]).unwrap();
+        assert_eq!(ChannelOrder::Rgb.apply_nchw(xs.clone()), xs);
+        let ys = ChannelOrder::Bgr.apply_nchw(xs);
+        assert_eq!(
+            ys.iter().copied().collect::<Vec<_>>(),
+            vec![3., 3., 2., 2., 1., 1.]
+        );
+    }
+}
@@ -59,10 +59,34 @@ impl DataLoader {
 
         match source {
             s if s.is_file() => paths.push_back(s.to_path_buf()),
+            // glob on file names, like `frames/*.jpg`
+            s if s
+                .file_name()
+                .is_some_and(|x| x.to_string_lossy().contains(['*', '?'])) =>
+            {
+                let pattern = s.file_name().unwrap().to_string_lossy().to_string();
+                let dir = match s.parent() {
+                    Some(x) if !x.as_os_str().is_empty() => x,
+                    _ => Path::new("."),
+                };
+                let mut xs = WalkDir::new(dir)
+                    .max_depth(if self.recursive { usize::MAX } else { 1 })
+                    .into_iter()
+                    .filter_entry(|e| e.depth() == 0 || !Self::_is_hidden(e))
+                    .filter_map(|e| e.ok())
+                    .filter(|e| {
+                        e.file_type().is_file()
+                            && Self::_wildcard(&pattern, &e.file_name().to_string_lossy())
+                    })
+                    .map(|e| e.path().to_path_buf())
+                    .collect::<Vec<_>>();
+                xs.sort();
+                paths.extend(xs);
+            }
             s if s.is_dir() => {
                 for entry in WalkDir::new(s)
                     .into_iter()
-                    .filter_entry(|e| !Self::_is_hidden(e))
+                    .filter_entry(|e| e.depth() == 0 || !Self::_is_hidden(e))
                 {
                     let entry = entry.unwrap();
                     if entry.file_type().is_dir() {
@@ -112,6 +136,30 @@ impl DataLoader {
         &self.paths
     }
 
+    /// `*` matches any run of chars, `?` a single one
+    fn _wildcard(pattern: &str, name: &str) -> bool {
+        let (p, n): (Vec<char>, Vec<char>) = (pattern.chars().collect(), name.chars().collect());
+        let (mut i, mut j) = (0, 0);
+        let mut star: Option<(usize, usize)> = None;
+        while j < n.len() {
+            if i < p.len() && (p[i] == '?' || p[i] == n[j]) {
+                i += 1;
+                j += 1;
+            } else if i < p.len() && p[i] == '*' {
+                star = Some((i, j));
+                i += 1;
+            } else if let Some((si, sj)) = star {
+                i = si + 1;
+                j = sj + 1;
+                star = Some((si, sj + 1));
+            } else {
+                return false;
+            }
+        }
+        p[i..].iter().all(|&c| c == '*')
+    }
+
+    /// Dot-files and dot-directories, never applied to the walk root which may be `.` or `.cache`
     fn _is_hidden(entry: &DirEntry) -> bool {
         entry
             .file_name()
@@ -120,3 +168,38 @@ impl DataLoader {
             .unwrap_or(false)
     }
 }
+
+#[cfg(test)]
+mod tests_dataloader {
+    use super::DataLoader;
+
+    #[test]
+    fn wildcard() {
+        assert!(DataLoader::_wildcard("*.jpg", "a.jpg"));
+        assert!(DataLoader::_wildcard("frame_??.png", "frame_01.png"));
+        assert!(DataLoader::_wildcard("*", ""));
+        assert!(DataLoader::_wildcard("a*b*c", "aXXbYc"));
+        assert!(!DataLoader::_wildcard("*.jpg", "a.png"));
+        assert!(!DataLoader::_wildcard("frame_?.png", "frame_01.png"));
+    }
+
+    #[test]
+    fn load_pattern() {
+        // bare pattern: walked from `.`, the package root under `cargo test`
+        let dl = DataLoader::default().load("*.toml").unwrap();
+        assert!(dl.paths.iter().any(|x| x.ends_with("Cargo.toml")));
+
+        // pattern under a hidden directory
+        let dir = std::env::temp_dir().join(format!("usls-dl-{}", std::process::id()));
+        let hidden = dir.join(".cache");
+        std::fs::create_dir_all(&hidden).unwrap();
+        for name in ["a.jpg", "b.jpg", "c.png", ".d.jpg"] {
+            std::fs::write(hidden.join(name), b"").unwrap();
+        }
+        let dl = DataLoader::default().load(hidden.join("*.jpg")).unwrap();
+        assert_eq!(dl.paths.len(), 2);
+        let dl = DataLoader::default().load(&hidden).unwrap();
+        assert_eq!(dl.paths.len(), 3);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,8 @@
+use anyhow::{Context, Result};
 use std::ops::Index;
+use std::path::Path;
+
+use crate::SAFE_CROSS_MARK;
 
 /// Dynamic Confidences
 #[derive(Clone, PartialEq, PartialOrd)]
@@ -52,4 +56,71 @@ impl DynConf {
 
         Self { confs }
     }
+
+    /// Per-class thresholds from a file of `name threshold` or `name,threshold` lines,
+    /// classes not listed fall back to `default` as in `new`
+    pub fn from_file<P: AsRef<Path>>(path: P, names: &[String], default: &[f32]) -> Result<Self> {
+        let path = path.as_ref();
+        let s = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read confs file: {}", path.display()))?;
+        Self::parse(&s, names, default)
+            .with_context(|| format!("Failed to parse confs file: {}", path.display()))
+    }
+
+    fn parse(s: &str, names: &[String], default: &[f32]) -> Result<Self> {
+        let mut confs = Self::new(default, names.len()).confs;
+        let lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+        for (k, (i, line)) in lines.enumerate() {
+            // names may contain spaces, e.g. `traffic light 0.3`
+            let (name, conf) = line
+                .rsplit_once(',')
+                .or_else(|| line.rsplit_once(char::is_whitespace))
+                .with_context(|| format!("Line {i}: expected `name threshold`, got `{line}`"))?;
+            let (name, conf) = (name.trim(), conf.trim());
+            let conf: f32 = match conf.parse() {
+                Ok(conf) => conf,
+                Err(_) if k == 0 => continue, // csv header
+                Err(_) => anyhow::bail!("Line {i}: invalid threshold `{conf}`"),
+            };
+            if !(0. ..=1.).contains(&conf) {
+                anyhow::bail!("Line {i}: threshold should be in [0, 1], got {conf}");
+            }
+            match names
+                .iter()
+                .position(|x| x == name)
+                .or_else(|| name.parse::<usize>().ok().filter(|&id| id < names.len()))
+            {
+                Some(id) => confs[id] = conf,
+                None => println!("{SAFE_CROSS_MARK} Line {i}: unknown class `{name}`, ignored"),
+            }
+        }
+        Ok(Self { confs })
+    }
+}
+
+#[cfg(test)]
+mod tests_dynconf {
+    use super::DynConf;
+
+    #[test]
+    fn parse() {
+        let names: Vec<String> = ["person", "traffic light", "dog"]
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        let s = "name,threshold\n# noisy classes\ntraffic light, 0.6\n\ncat,0.9\n2,0.1\n";
+        let confs = DynConf::parse(s, &names, &[0.25]).unwrap();
+        assert_eq!((confs[0], confs[1], confs[2]), (0.25, 0.6, 0.1));
+
+        let confs = DynConf::parse("dog 0.3", &names, &[0.4, 0.5]).unwrap();
+        assert_eq!((confs[0], confs[1], confs[2]), (0.4, 0.5, 0.3));
+
+        assert!(DynConf::parse("dog 0.3\nperson x", &names, &[0.4]).is_err());
+        assert!(DynConf::parse("dog 1.3", &names, &[0.4]).is_err());
+        assert!(DynConf::parse("dog", &names, &[0.4]).is_err());
+    }
 }
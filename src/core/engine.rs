@@ -7,7 +7,7 @@ use ort::{
     MINOR_VERSION,
 };
 use prost::Message;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::{
     home_dir, onnx, ops::make_divisible, Device, MinOptMax, Options, Ts, CHECK_MARK, CROSS_MARK,
@@ -39,8 +39,11 @@ pub struct OrtEngine {
 
 impl OrtEngine {
     pub fn new(config: &Options) -> Result<Self> {
-        // onnx graph
-        let model_proto = Self::load_onnx(&config.onnx_path)?;
+        // onnx graph, decompressed in memory if needed
+        let model_bytes = Self::read_model(&config.onnx_path)?;
+        let model_proto = onnx::ModelProto::decode(model_bytes.as_slice())?;
+        // plain models are committed from file, so external data next to them still resolves
+        let model_bytes = Self::is_compressed(&config.onnx_path).then_some(model_bytes);
         let graph = match &model_proto.graph {
             Some(graph) => graph,
             None => anyhow::bail!("No graph found in this proto"),
@@ -67,6 +70,15 @@ impl OrtEngine {
         let inputs_attrs = Self::io_from_onnx_value_info(&initializer_names, &graph.input)?;
         let outputs_attrs = Self::io_from_onnx_value_info(&initializer_names, &graph.output)?;
 
+        // inputs dtypes check
+        for (name, dtype) in inputs_attrs.names.iter().zip(inputs_attrs.dtypes.iter()) {
+            if !Self::is_input_dtype_supported(dtype) {
+                anyhow::bail!(
+                    "{CROSS_MARK} Unsupported input dtype: {dtype:?} of input {name:?}. Supported: Float32, Float16, Int32, Int64, Uint8"
+                );
+            }
+        }
+
         // inputs minoptmax
         let mut inputs_minoptmax: Vec<Vec<MinOptMax>> = Vec::new();
         for (i, dims) in inputs_attrs.dimss.iter().enumerate() {
@@ -79,30 +91,30 @@ impl OrtEngine {
                 )
                     .into();
                 let x: MinOptMax = match (i, ii) {
-                    (0, 0) => Self::_set_ixx(x, &config.i00, i, ii).unwrap_or(x_default),
-                    (0, 1) => Self::_set_ixx(x, &config.i01, i, ii).unwrap_or(x_default),
-                    (0, 2) => Self::_set_ixx(x, &config.i02, i, ii).unwrap_or(x_default),
-                    (0, 3) => Self::_set_ixx(x, &config.i03, i, ii).unwrap_or(x_default),
-                    (0, 4) => Self::_set_ixx(x, &config.i04, i, ii).unwrap_or(x_default),
-                    (0, 5) => Self::_set_ixx(x, &config.i05, i, ii).unwrap_or(x_default),
-                    (1, 0) => Self::_set_ixx(x, &config.i10, i, ii).unwrap_or(x_default),
-                    (1, 1) => Self::_set_ixx(x, &config.i11, i, ii).unwrap_or(x_default),
-                    (1, 2) => Self::_set_ixx(x, &config.i12, i, ii).unwrap_or(x_default),
-                    (1, 3) => Self::_set_ixx(x, &config.i13, i, ii).unwrap_or(x_default),
-                    (1, 4) => Self::_set_ixx(x, &config.i14, i, ii).unwrap_or(x_default),
-                    (1, 5) => Self::_set_ixx(x, &config.i15, i, ii).unwrap_or(x_default),
-                    (2, 0) => Self::_set_ixx(x, &config.i20, i, ii).unwrap_or(x_default),
-                    (2, 1) => Self::_set_ixx(x, &config.i21, i, ii).unwrap_or(x_default),
-                    (2, 2) => Self::_set_ixx(x, &config.i22, i, ii).unwrap_or(x_default),
-                    (2, 3) => Self::_set_ixx(x, &config.i23, i, ii).unwrap_or(x_default),
-                    (2, 4) => Self::_set_ixx(x, &config.i24, i, ii).unwrap_or(x_default),
-                    (2, 5) => Self::_set_ixx(x, &config.i25, i, ii).unwrap_or(x_default),
-                    (3, 0) => Self::_set_ixx(x, &config.i30, i, ii).unwrap_or(x_default),
-                    (3, 1) => Self::_set_ixx(x, &config.i31, i, ii).unwrap_or(x_default),
-                    (3, 2) => Self::_set_ixx(x, &config.i32_, i, ii).unwrap_or(x_default),
-                    (3, 3) => Self::_set_ixx(x, &config.i33, i, ii).unwrap_or(x_default),
-                    (3, 4) => Self::_set_ixx(x, &config.i34, i, ii).unwrap_or(x_default),
-                    (3, 5) => Self::_set_ixx(x, &config.i35, i, ii).unwrap_or(x_default),
+                    (0, 0) => Self::_set_ixx(x, &config.i00, i, ii)?.unwrap_or(x_default),
+                    (0, 1) => Self::_set_ixx(x, &config.i01, i, ii)?.unwrap_or(x_default),
+                    (0, 2) => Self::_set_ixx(x, &config.i02, i, ii)?.unwrap_or(x_default),
+                    (0, 3) => Self::_set_ixx(x, &config.i03, i, ii)?.unwrap_or(x_default),
+                    (0, 4) => Self::_set_ixx(x, &config.i04, i, ii)?.unwrap_or(x_default),
+                    (0, 5) => Self::_set_ixx(x, &config.i05, i, ii)?.unwrap_or(x_default),
+                    (1, 0) => Self::_set_ixx(x, &config.i10, i, ii)?.unwrap_or(x_default),
+                    (1, 1) => Self::_set_ixx(x, &config.i11, i, ii)?.unwrap_or(x_default),
+                    (1, 2) => Self::_set_ixx(x, &config.i12, i, ii)?.unwrap_or(x_default),
+                    (1, 3) => Self::_set_ixx(x, &config.i13, i, ii)?.unwrap_or(x_default),
+                    (1, 4) => Self::_set_ixx(x, &config.i14, i, ii)?.unwrap_or(x_default),
+                    (1, 5) => Self::_set_ixx(x, &config.i15, i, ii)?.unwrap_or(x_default),
+                    (2, 0) => Self::_set_ixx(x, &config.i20, i, ii)?.unwrap_or(x_default),
+                    (2, 1) => Self::_set_ixx(x, &config.i21, i, ii)?.unwrap_or(x_default),
+                    (2, 2) => Self::_set_ixx(x, &config.i22, i, ii)?.unwrap_or(x_default),
+                    (2, 3) => Self::_set_ixx(x, &config.i23, i, ii)?.unwrap_or(x_default),
+                    (2, 4) => Self::_set_ixx(x, &config.i24, i, ii)?.unwrap_or(x_default),
+                    (2, 5) => Self::_set_ixx(x, &config.i25, i, ii)?.unwrap_or(x_default),
+                    (3, 0) => Self::_set_ixx(x, &config.i30, i, ii)?.unwrap_or(x_default),
+                    (3, 1) => Self::_set_ixx(x, &config.i31, i, ii)?.unwrap_or(x_default),
+                    (3, 2) => Self::_set_ixx(x, &config.i32_, i, ii)?.unwrap_or(x_default),
+                    (3, 3) => Self::_set_ixx(x, &config.i33, i, ii)?.unwrap_or(x_default),
+                    (3, 4) => Self::_set_ixx(x, &config.i34, i, ii)?.unwrap_or(x_default),
+                    (3, 5) => Self::_set_ixx(x, &config.i35, i, ii)?.unwrap_or(x_default),
                     _ => todo!(),
                 };
                 v_.push(x);
@@ -124,14 +136,20 @@ impl OrtEngine {
                     config.trt_int8_enable,
                     config.trt_fp16_enable,
                     config.trt_engine_cache_enable,
-                )?;
-            }
-            Device::Cuda(device_id) => {
-                Self::build_cuda(&builder, device_id).unwrap_or_else(|err| {
+                )
+                .unwrap_or_else(|err| {
                     device = Device::Cpu(0);
                     println!("{err}");
-                })
+                });
+                if config.deterministic && matches!(device, Device::Trt(_)) {
+                    println!("{CROSS_MARK} TensorRT builds are not deterministic across machines, use CUDA instead");
+                }
             }
+            Device::Cuda(device_id) => Self::build_cuda(&builder, device_id, config.deterministic)
+                .unwrap_or_else(|err| {
+                    device = Device::Cpu(0);
+                    println!("{err}");
+                }),
             Device::CoreML(_) => Self::build_coreml(&builder).unwrap_or_else(|err| {
                 device = Device::Cpu(0);
                 println!("{err}");
@@ -142,9 +160,20 @@ impl OrtEngine {
             _ => todo!(),
         }
 
-        let session = builder
-            .with_optimization_level(ort::GraphOptimizationLevel::Level3)?
-            .commit_from_file(&config.onnx_path)?;
+        let mut builder = builder.with_optimization_level(config.graph_opt_level.to_ort())?;
+        if let Some(n) = config.intra_threads {
+            builder = builder.with_intra_threads(n)?;
+        }
+        if let Some(n) = config.inter_threads {
+            // only used by the parallel executor
+            builder = builder
+                .with_parallel_execution(true)?
+                .with_inter_threads(n)?;
+        }
+        let session = match &model_bytes {
+            Some(model_bytes) => builder.commit_from_memory(model_bytes)?,
+            None => builder.commit_from_file(&config.onnx_path)?,
+        };
 
         // summary
         println!(
@@ -229,8 +258,14 @@ impl OrtEngine {
         }
     }
 
-    fn build_cuda(builder: &SessionBuilder, device_id: usize) -> Result<()> {
-        let ep = ort::CUDAExecutionProvider::default().with_device_id(device_id as i32);
+    fn build_cuda(builder: &SessionBuilder, device_id: usize, deterministic: bool) -> Result<()> {
+        let mut ep = ort::CUDAExecutionProvider::default().with_device_id(device_id as i32);
+        if deterministic {
+            ep = ep
+                .with_conv_algorithm_search(ort::CUDAExecutionProviderCuDNNConvAlgoSearch::Default)
+                .with_conv_max_workspace(false)
+                .with_copy_in_default_stream(true);
+        }
         if ep.is_available()? && ep.register(builder).is_ok() {
             Ok(())
         } else {
@@ -248,7 +283,7 @@ impl OrtEngine {
     }
 
     fn build_cpu(builder: &SessionBuilder) -> Result<()> {
-        let ep = ort::CUDAExecutionProvider::default();
+        let ep = ort::CPUExecutionProvider::default();
         if ep.is_available()? && ep.register(builder).is_ok() {
             Ok(())
         } else {
@@ -292,7 +327,12 @@ impl OrtEngine {
                 TensorElementType::Int64 => {
                     ort::Value::from_array(x.mapv(|x_| x_ as i64).view())?.into_dyn()
                 }
-                _ => todo!(),
+                TensorElementType::Uint8 => {
+                    // expects values in [0, 255], normalization is done in-graph
+                    ort::Value::from_array(x.mapv(|x_| x_.round().clamp(0., 255.) as u8).view())?
+                        .into_dyn()
+                }
+                _ => anyhow::bail!("{CROSS_MARK} Unsupported input dtype: {idtype:?}"),
             };
             xs_.push(Into::<ort::SessionInputValue<'_>>::into(x_));
         }
@@ -328,6 +368,12 @@ impl OrtEngine {
                     .to_owned()
                     .mapv(|x| x as f32)
                     .into_owned(),
+                TensorElementType::Int32 => y
+                    .try_extract_tensor::<i32>()?
+                    .view()
+                    .to_owned()
+                    .mapv(|x| x as f32)
+                    .into_owned(),
                 _ => todo!(),
             };
             ys.push(y_);
@@ -353,22 +399,46 @@ impl OrtEngine {
         Ok(ys)
     }
 
-    pub fn _set_ixx(x: isize, ixx: &Option<MinOptMax>, i: usize, ii: usize) -> Option<MinOptMax> {
+    pub fn _set_ixx(
+        x: isize,
+        ixx: &Option<MinOptMax>,
+        i: usize,
+        ii: usize,
+    ) -> Result<Option<MinOptMax>> {
         match x {
-            -1 => {
-                match ixx {
-                    None => panic!(
-                        "{CROSS_MARK} Using dynamic shapes in inputs without specifying it: the {}-th input, the {}-th dimension.",
-                        i + 1,
-                        ii + 1
-                    ),
-                    Some(ixx) => Some(ixx.to_owned()), // customized
+            -1 => match ixx {
+                None => anyhow::bail!(
+                    "{CROSS_MARK} Using dynamic shapes in inputs without specifying it: the {}-th input, the {}-th dimension.",
+                    i + 1,
+                    ii + 1
+                ),
+                Some(ixx) => {
+                    // customized
+                    ixx.validate().map_err(|err| {
+                        anyhow::anyhow!(
+                            "{CROSS_MARK} Invalid shape of the {}-th input, the {}-th dimension: {err}",
+                            i + 1,
+                            ii + 1
+                        )
+                    })?;
+                    Ok(Some(ixx.to_owned()))
                 }
-            }
-            _ => Some((x, x, x).into()), // customized, but not dynamic
+            },
+            _ => Ok(Some((x, x, x).into())), // customized, but not dynamic
         }
     }
 
+    pub fn is_input_dtype_supported(x: &ort::TensorElementType) -> bool {
+        matches!(
+            x,
+            TensorElementType::Float32
+                | TensorElementType::Float16
+                | TensorElementType::Int32
+                | TensorElementType::Int64
+                | TensorElementType::Uint8
+        )
+    }
+
     pub fn nbytes_from_onnx_dtype_id(x: usize) -> usize {
         match x {
             7 | 11 | 13 => 8,     // i64, f64, u64
@@ -529,10 +599,38 @@ impl OrtEngine {
     }
 
     pub fn load_onnx<P: AsRef<std::path::Path>>(p: P) -> Result<onnx::ModelProto> {
-        let f = std::fs::read(p)?;
+        let f = Self::read_model(p)?;
         Ok(onnx::ModelProto::decode(f.as_slice())?)
     }
 
+    /// Whether the model is stored as `.gz` or `.zst`
+    pub fn is_compressed<P: AsRef<std::path::Path>>(p: P) -> bool {
+        matches!(
+            p.as_ref().extension().and_then(|x| x.to_str()),
+            Some("gz") | Some("zst")
+        )
+    }
+
+    /// Read model bytes, `.gz` and `.zst` are decompressed
+    pub fn read_model<P: AsRef<std::path::Path>>(p: P) -> Result<Vec<u8>> {
+        let p = p.as_ref();
+        let f = std::fs::read(p)?;
+        match p.extension().and_then(|x| x.to_str()) {
+            Some("gz") => {
+                let mut buf = Vec::new();
+                std::io::Read::read_to_end(
+                    &mut flate2::read::GzDecoder::new(f.as_slice()),
+                    &mut buf,
+                )
+                .map_err(|err| anyhow::anyhow!("Failed to decompress {p:?}: {err}"))?;
+                Ok(buf)
+            }
+            Some("zst") => zstd::decode_all(f.as_slice())
+                .map_err(|err| anyhow::anyhow!("Failed to decompress {p:?}: {err}")),
+            _ => Ok(f),
+        }
+    }
+
     pub fn oshapes(&self) -> &Vec<Vec<isize>> {
         &self.outputs_attrs.dimss
     }
@@ -599,6 +697,16 @@ impl OrtEngine {
         }
     }
 
+    /// Custom metadata of the model, e.g. `names`, `task` or `kpt_shape` of YOLO exports.
+    /// Producer and versions are in `producer_name`, `producer_version` and `model_version`.
+    pub fn metadata(&self) -> HashMap<String, String> {
+        self.model_proto
+            .metadata_props
+            .iter()
+            .map(|x| (x.key.to_owned(), x.value.to_owned()))
+            .collect()
+    }
+
     pub fn session(&self) -> &Session {
         &self.session
     }
@@ -631,3 +739,27 @@ impl OrtEngine {
         self.wbmems
     }
 }
+
+#[cfg(test)]
+mod tests_engine {
+    use super::OrtEngine;
+    use crate::MinOptMax;
+
+    #[test]
+    fn set_ixx() {
+        let x = OrtEngine::_set_ixx(3, &None, 0, 1).unwrap().unwrap();
+        assert_eq!((x.min, x.opt, x.max), (3, 3, 3));
+        let x = OrtEngine::_set_ixx(-1, &Some((1, 2, 4).into()), 0, 0)
+            .unwrap()
+            .unwrap();
+        assert_eq!((x.min, x.opt, x.max), (1, 2, 4));
+        assert!(OrtEngine::_set_ixx(-1, &None, 0, 2).is_err());
+        assert!(OrtEngine::_set_ixx(-1, &Some(MinOptMax::default()), 0, 2).is_err());
+        let x = MinOptMax {
+            min: 8,
+            opt: 4,
+            max: 2,
+        };
+        assert!(OrtEngine::_set_ixx(-1, &Some(x), 0, 2).is_err());
+    }
+}
@@ -0,0 +1,353 @@
+//! Exporters of `Y`s for datasets & analysis
+
+use anyhow::Result;
+use ndarray::{Array, IxDyn};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::{coco, Y};
+
+fn csv_field(x: &str) -> String {
+    if x.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", x.replace('"', "\"\""))
+    } else {
+        x.to_string()
+    }
+}
+
+/// CSV manifest, one row per bbox: `image_path,class,conf,x1,y1,x2,y2`
+///
+/// Images without detections get a row with empty fields if `with_empty`.
+pub fn csv<W: Write>(ys: &[Y], paths: &[PathBuf], mut writer: W, with_empty: bool) -> Result<()> {
+    if ys.len() != paths.len() {
+        anyhow::bail!("Got {} results but {} paths", ys.len(), paths.len());
+    }
+    writeln!(writer, "image_path,class,conf,x1,y1,x2,y2")?;
+    for (y, path) in ys.iter().zip(paths.iter()) {
+        let path = csv_field(&path.to_string_lossy());
+        let bboxes = y.bboxes().map(|x| x.as_slice()).unwrap_or_default();
+        if bboxes.is_empty() {
+            if with_empty {
+                writeln!(writer, "{path},,,,,,")?;
+            }
+            continue;
+        }
+        for bbox in bboxes.iter() {
+            let class = match bbox.name() {
+                Some(name) => csv_field(name),
+                None => bbox.id().to_string(),
+            };
+            writeln!(
+                writer,
+                "{path},{class},{:.4},{:.2},{:.2},{:.2},{:.2}",
+                bbox.confidence(),
+                bbox.xmin(),
+                bbox.ymin(),
+                bbox.xmax(),
+                bbox.ymax(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn round(x: f32, decimals: i32) -> f64 {
+    let p = 10f64.powi(decimals);
+    (x as f64 * p).round() / p
+}
+
+/// How class ids become COCO `category_id`s
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CategoryMap {
+    /// The model's class id, i.e. the index into its names
+    #[default]
+    Id,
+    /// Class ids of `coco::NAMES_80` onto the official 91-category numbering
+    Coco80,
+}
+
+impl CategoryMap {
+    pub fn category_id(&self, id: isize) -> Result<usize> {
+        let id = usize::try_from(id).map_err(|_| anyhow::anyhow!("Invalid class id {id}"))?;
+        match self {
+            Self::Id => Ok(id),
+            Self::Coco80 => match coco::CATEGORY_IDS_80.get(id) {
+                Some(&x) => Ok(x),
+                None => anyhow::bail!("Class id {id} is out of the 80 COCO classes"),
+            },
+        }
+    }
+}
+
+/// One detection in the COCO results format
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CocoDetection {
+    pub image_id: u64,
+    pub category_id: usize,
+    pub bbox: [f32; 4], // [x, y, w, h]
+    pub score: f32,
+}
+
+impl CocoDetection {
+    pub(crate) fn from_bbox(image_id: u64, x: &crate::Bbox, map: CategoryMap) -> Result<Self> {
+        Ok(Self {
+            image_id,
+            category_id: map.category_id(x.id())?,
+            bbox: [x.xmin(), x.ymin(), x.width(), x.height()],
+            score: x.confidence(),
+        })
+    }
+
+    pub fn json(&self) -> Value {
+        json!({
+            "image_id": self.image_id,
+            "category_id": self.category_id,
+            "bbox": self.bbox.map(|x| round(x, 2)),
+            "score": round(self.score, 5),
+        })
+    }
+}
+
+/// COCO detection results of a dataset, the JSON array `pycocotools`' `loadRes` expects
+pub fn coco<W: Write>(ys: &[Y], image_ids: &[u64], map: CategoryMap, writer: W) -> Result<()> {
+    if ys.len() != image_ids.len() {
+        anyhow::bail!("Got {} results but {} image ids", ys.len(), image_ids.len());
+    }
+    let mut dets = Vec::new();
+    for (y, &image_id) in ys.iter().zip(image_ids.iter()) {
+        dets.extend(y.to_coco(image_id, map)?.iter().map(|x| x.json()));
+    }
+    serde_json::to_writer(writer, &dets)?;
+    Ok(())
+}
+
+/// COCO segmentation results of a dataset, from RLEs or else masks, with compressed counts
+pub fn coco_segm<W: Write>(ys: &[Y], image_ids: &[u64], map: CategoryMap, writer: W) -> Result<()> {
+    if ys.len() != image_ids.len() {
+        anyhow::bail!("Got {} results but {} image ids", ys.len(), image_ids.len());
+    }
+    let mut dets = Vec::new();
+    for (y, &image_id) in ys.iter().zip(image_ids.iter()) {
+        let rles = match y.rles() {
+            Some(x) => x.clone(),
+            None => y.masks_to_rle(),
+        };
+        for x in rles.iter() {
+            dets.push(json!({
+                "image_id": image_id,
+                "category_id": map.category_id(x.id())?,
+                "segmentation": {"size": x.size(), "counts": x.to_coco_string()},
+                "score": round(x.confidence(), 5),
+            }));
+        }
+    }
+    serde_json::to_writer(writer, &dets)?;
+    Ok(())
+}
+
+/// Newline-delimited JSON sink, one `Y` per line as serialized by serde, flushed per line
+///
+/// ```ignore
+/// let mut sink = export::Ndjson::new(std::io::stdout().lock());
+/// for (xs, paths) in dl {
+///     for (y, path) in model.run(&xs)?.iter().zip(paths.iter()) {
+///         sink.write(Some(path), y)?;
+///     }
+/// }
+/// ```
+#[cfg(feature = "serde")]
+pub struct Ndjson<W: Write> {
+    writer: W,
+}
+
+/// One line of `Ndjson`: the `Y` fields, plus `image_path` if any
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct NdjsonLine<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image_path: Option<&'a std::path::Path>,
+    #[serde(flatten)]
+    y: &'a Y,
+}
+
+#[cfg(feature = "serde")]
+impl<W: Write> Ndjson<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write(&mut self, image_path: Option<&std::path::Path>, y: &Y) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, &NdjsonLine { image_path, y })?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Tensor in `.npy` (v1.0, little-endian f32), e.g. for diffing against numpy
+pub fn npy<W: Write>(x: &Array<f32, IxDyn>, mut writer: W) -> Result<()> {
+    let shape = match x.shape() {
+        [n] => format!("({n},)"),
+        dims => format!(
+            "({})",
+            dims.iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+    let mut header = format!("{{'descr': '<f4', 'fortran_order': False, 'shape': {shape}, }}");
+    // magic(6) + version(2) + len(2) + header, aligned to 64 and ends with '\n'
+    let pad = 64 - (10 + header.len() + 1) % 64;
+    header.push_str(&" ".repeat(pad % 64));
+    header.push('\n');
+
+    writer.write_all(b"\x93NUMPY\x01\x00")?;
+    writer.write_all(&(header.len() as u16).to_le_bytes())?;
+    writer.write_all(header.as_bytes())?;
+    for v in x.iter() {
+        writer.write_all(&v.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests_export {
+    use super::{coco, coco_segm, csv, npy, CategoryMap};
+    use crate::{Bbox, Mask, Y};
+    use std::path::PathBuf;
+
+    #[test]
+    fn csv_rows() {
+        let ys = vec![
+            Y::default().with_bboxes(&[Bbox::default()
+                .with_xyxy(1., 2., 3., 4.)
+                .with_id(0)
+                .with_confidence(0.5)
+                .with_name(Some("person, adult".to_string()))]),
+            Y::default(),
+        ];
+        let paths = vec![PathBuf::from("a.jpg"), PathBuf::from("b.jpg")];
+
+        let mut buf = Vec::new();
+        csv(&ys, &paths, &mut buf, true).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "image_path,class,conf,x1,y1,x2,y2\n\
+             a.jpg,\"person, adult\",0.5000,1.00,2.00,3.00,4.00\n\
+             b.jpg,,,,,,\n"
+        );
+
+        let mut buf = Vec::new();
+        csv(&ys, &paths, &mut buf, false).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap().lines().count(), 2);
+        assert!(csv(&ys, &paths[..1], Vec::new(), false).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ndjson_lines() {
+        let mut sink = super::Ndjson::new(Vec::new());
+        let y = Y::default().with_bboxes(&[Bbox::default()
+            .with_xyxy(1., 2., 3.5, 4.)
+            .with_id(2)
+            .with_confidence(0.9)]);
+        sink.write(Some(std::path::Path::new("a.jpg")), &y).unwrap();
+        sink.write(None, &Y::default()).unwrap();
+        let out = String::from_utf8(sink.into_inner()).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        let v: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(v["image_path"], "a.jpg");
+        assert_eq!(v["bboxes"][0]["id"], 2);
+        // same schema as serializing `Y`
+        let mut v = v;
+        v.as_object_mut().unwrap().remove("image_path");
+        assert_eq!(serde_json::from_value::<Y>(v).unwrap(), y);
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(lines[1]).unwrap(),
+            serde_json::to_value(Y::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn npy_layout() {
+        let x = ndarray::Array::from_shape_vec((2, 3), vec![0f32, 1., 2., 3., 4., 5.])
+            .unwrap()
+            .into_dyn();
+        let mut buf = Vec::new();
+        npy(&x, &mut buf).unwrap();
+        assert_eq!(&buf[..8], b"\x93NUMPY\x01\x00");
+        let header_len = u16::from_le_bytes([buf[8], buf[9]]) as usize;
+        assert_eq!((10 + header_len) % 64, 0);
+        let header = std::str::from_utf8(&buf[10..10 + header_len]).unwrap();
+        assert!(header.contains("'shape': (2, 3)"));
+        assert!(header.ends_with('\n'));
+        assert_eq!(buf.len(), 10 + header_len + 6 * 4);
+        assert_eq!(&buf[buf.len() - 4..], &5f32.to_le_bytes());
+    }
+
+    #[test]
+    fn coco_results() {
+        let bbox = |name: &str, id: isize| {
+            Bbox::default()
+                .with_xyxy(10., 20., 40., 60.)
+                .with_id(id)
+                .with_confidence(0.5)
+                .with_name(Some(name.to_string()))
+        };
+        // class 4 is "airplane" in NAMES_80 but category 5 in COCO; names are never looked at
+        let ys = vec![
+            Y::default().with_bboxes(&[bbox("airplane", 4), bbox("person", 2)]),
+            Y::default(),
+        ];
+        let dets = ys[0].to_coco(42, CategoryMap::Id).unwrap();
+        assert_eq!((dets[0].category_id, dets[1].category_id), (4, 2));
+        assert_eq!(dets[0].bbox, [10., 20., 30., 40.]);
+        let dets = ys[0].to_coco(42, CategoryMap::Coco80).unwrap();
+        assert_eq!((dets[0].category_id, dets[1].category_id), (5, 3));
+        let y = Y::default().with_bboxes(&[bbox("widget", 80)]);
+        assert!(y.to_coco(42, CategoryMap::Coco80).is_err());
+        assert!(y.to_coco(42, CategoryMap::Id).is_ok());
+
+        let mut buf = Vec::new();
+        coco(&ys, &[42, 43], CategoryMap::Id, &mut buf).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(v.as_array().unwrap().len(), 2);
+        assert_eq!(v[0]["image_id"], 42);
+        assert_eq!(v[0]["bbox"], serde_json::json!([10.0, 20.0, 30.0, 40.0]));
+        assert!(coco(&ys, &[42], CategoryMap::Id, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn coco_segm_results() {
+        let mut luma = image::GrayImage::new(2, 3);
+        luma.put_pixel(0, 1, image::Luma([255]));
+        let y = Y::default().with_masks(&[Mask::default()
+            .with_mask(image::DynamicImage::from(luma))
+            .with_id(0)
+            .with_name(Some("person".to_string()))]);
+        let mut buf = Vec::new();
+        coco_segm(
+            std::slice::from_ref(&y),
+            &[7],
+            CategoryMap::Coco80,
+            &mut buf,
+        )
+        .unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(v[0]["category_id"], 1);
+        let mut buf = Vec::new();
+        coco_segm(&[y], &[7], CategoryMap::Id, &mut buf).unwrap();
+        let v: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(v[0]["category_id"], 0);
+        assert_eq!(v[0]["segmentation"]["size"], serde_json::json!([3, 2]));
+        assert_eq!(v[0]["segmentation"]["counts"], "114");
+    }
+}
@@ -0,0 +1,20 @@
+/// Graph optimizations applied by onnxruntime when the session is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphOptLevel {
+    Disable,  // fastest cold start
+    Basic,    // constant folding, redundant node removal
+    Extended, // node fusions
+    #[default]
+    All, // layout optimizations as well
+}
+
+impl GraphOptLevel {
+    pub fn to_ort(&self) -> ort::GraphOptimizationLevel {
+        match self {
+            Self::Disable => ort::GraphOptimizationLevel::Disable,
+            Self::Basic => ort::GraphOptimizationLevel::Level1,
+            Self::Extended => ort::GraphOptimizationLevel::Level2,
+            Self::All => ort::GraphOptimizationLevel::Level3,
+        }
+    }
+}
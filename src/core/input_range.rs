@@ -0,0 +1,54 @@
+use ndarray::{Array, IxDyn};
+
+/// Value range expected by the model input, from pixels in [0, 255]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InputRange {
+    Zero255, // [0, 255], as is
+    #[default]
+    ZeroOne, // [0, 1], YOLO exports
+    NegOneOne, // [-1, 1]
+}
+
+impl InputRange {
+    pub fn apply(&self, xs: Array<f32, IxDyn>) -> Array<f32, IxDyn> {
+        match self {
+            Self::Zero255 => xs,
+            Self::ZeroOne => xs / 255.,
+            Self::NegOneOne => xs / 127.5 - 1.,
+        }
+    }
+
+    /// Same as `apply` for inputs already in [0, 1], e.g. another model's output
+    pub fn apply_unit(&self, xs: Array<f32, IxDyn>) -> Array<f32, IxDyn> {
+        match self {
+            Self::Zero255 => xs * 255.,
+            Self::ZeroOne => xs,
+            Self::NegOneOne => xs * 2. - 1.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_input_range {
+    use super::InputRange;
+    use ndarray::{Array, IxDyn};
+
+    #[test]
+    fn unit_matches_pixels() {
+        let pixels = Array::from_shape_vec(IxDyn(&[3]), vec![0., 51., 255.]).unwrap();
+        for r in [
+            InputRange::Zero255,
+            InputRange::ZeroOne,
+            InputRange::NegOneOne,
+        ] {
+            let a = r.apply(pixels.clone());
+            let b = r.apply_unit(pixels.clone() / 255.);
+            assert!(
+                a.iter().zip(b.iter()).all(|(a, b)| (a - b).abs() < 1e-4),
+                "{r:?}"
+            );
+        }
+        let unit = Array::from_shape_vec(IxDyn(&[2]), vec![0.2, 0.7]).unwrap();
+        assert_eq!(InputRange::ZeroOne.apply_unit(unit.clone()), unit);
+    }
+}
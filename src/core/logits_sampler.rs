@@ -1,11 +1,13 @@
 use anyhow::Result;
 use rand::distributions::{Distribution, WeightedIndex};
+use rand::{rngs::StdRng, SeedableRng};
 
 /// Logits Sampler
 #[derive(Debug)]
 pub struct LogitsSampler {
     temperature: f32,
     p: f32,
+    rng: StdRng,
 }
 
 impl Default for LogitsSampler {
@@ -13,6 +15,7 @@ impl Default for LogitsSampler {
         Self {
             temperature: 1.0,
             p: 0.0,
+            rng: StdRng::from_entropy(),
         }
     }
 }
@@ -27,6 +30,12 @@ impl LogitsSampler {
         self
     }
 
+    /// Reproducible top-p sampling
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
     pub fn with_temperature(mut self, temperature: f32) -> Self {
         self.temperature = temperature.max(1e-7);
         self
@@ -77,8 +86,7 @@ impl LogitsSampler {
         let choices: Vec<usize> = candidates.iter().map(|&(idx, _)| idx).collect();
         let probs: Vec<f32> = candidates.iter().map(|&(_, prob)| prob).collect();
         let dist = WeightedIndex::new(probs)?;
-        let mut rng = rand::thread_rng();
-        let token_id = choices[dist.sample(&mut rng)];
+        let token_id = choices[dist.sample(&mut self.rng)];
         Ok(token_id as u32)
     }
 
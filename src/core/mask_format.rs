@@ -0,0 +1,8 @@
+/// How segmentation masks are materialized in `Y`
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum MaskFormat {
+    Bitmap, // `Mask`s
+    #[default]
+    Polygon, // `Polygon`s, the largest contour of each instance
+    Rle,    // `Rle`s, COCO run-length encoding
+}
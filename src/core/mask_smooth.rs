@@ -0,0 +1,34 @@
+/// Smoothing applied on upscaled segmentation masks
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskSmooth {
+    pub open: u8,   // radius of morphological opening, 0 to skip
+    pub close: u8,  // radius of morphological closing, 0 to skip
+    pub sigma: f32, // std of gaussian blur, <= 0 to skip
+}
+
+impl Default for MaskSmooth {
+    fn default() -> Self {
+        Self {
+            open: 1,
+            close: 1,
+            sigma: 1.5,
+        }
+    }
+}
+
+impl MaskSmooth {
+    pub fn with_open(mut self, x: u8) -> Self {
+        self.open = x;
+        self
+    }
+
+    pub fn with_close(mut self, x: u8) -> Self {
+        self.close = x;
+        self
+    }
+
+    pub fn with_sigma(mut self, x: f32) -> Self {
+        self.sigma = x;
+        self
+    }
+}
@@ -26,6 +26,12 @@ impl std::fmt::Debug for MinOptMax {
     }
 }
 
+impl std::fmt::Display for MinOptMax {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}/{}", self.min, self.opt, self.max)
+    }
+}
+
 impl From<(isize, isize, isize)> for MinOptMax {
     fn from((min, opt, max): (isize, isize, isize)) -> Self {
         let min = min.min(opt);
@@ -50,4 +56,80 @@ impl MinOptMax {
             max: opt,
         }
     }
+
+    /// Whether the value may vary at runtime
+    pub fn is_dynamic(&self) -> bool {
+        self.min != self.max
+    }
+
+    /// Error if still the `-1` default or not ordered as `min <= opt <= max`
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.min < 0 || self.opt < 0 || self.max < 0 {
+            anyhow::bail!("MinOptMax {self} is uninitialized, a concrete size is required");
+        }
+        if self.min > self.opt || self.opt > self.max {
+            anyhow::bail!("MinOptMax {self} must satisfy min <= opt <= max");
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for MinOptMax {
+    type Err = anyhow::Error;
+
+    /// Parse `"opt"` or `"min opt max"`, separated by whitespace or commas
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let xs = s
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse::<isize>()
+                    .map_err(|_| anyhow::anyhow!("Invalid MinOptMax value `{x}` in `{s}`"))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        match xs[..] {
+            [opt] => Ok(Self::new(opt)),
+            [min, opt, max] => Ok((min, opt, max).into()),
+            _ => anyhow::bail!(
+                "MinOptMax expects `opt` or `min opt max`, got {} values in `{s}`",
+                xs.len()
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_min_opt_max {
+    use super::MinOptMax;
+
+    #[test]
+    fn from_str() {
+        let x: MinOptMax = "640 640 1280".parse().unwrap();
+        assert_eq!((x.min, x.opt, x.max), (640, 640, 1280));
+        let x: MinOptMax = " 1,3, 8 ".parse().unwrap();
+        assert_eq!((x.min, x.opt, x.max), (1, 3, 8));
+        let x: MinOptMax = "8 4 2".parse().unwrap();
+        assert_eq!((x.min, x.opt, x.max), (4, 4, 4));
+        let x: MinOptMax = "-1".parse().unwrap();
+        assert_eq!((x.min, x.opt, x.max), (-1, -1, -1));
+        assert!("".parse::<MinOptMax>().is_err());
+        assert!("1 2".parse::<MinOptMax>().is_err());
+        assert!("1 a 3".parse::<MinOptMax>().is_err());
+    }
+
+    #[test]
+    fn validate() {
+        let x = MinOptMax::from((320, 640, 1280));
+        assert_eq!(x.to_string(), "320/640/1280");
+        assert!(x.is_dynamic());
+        assert!(x.validate().is_ok());
+        assert!(!MinOptMax::new(640).is_dynamic());
+        assert!(MinOptMax::default().validate().is_err());
+        let x = MinOptMax {
+            min: 8,
+            opt: 4,
+            max: 2,
+        };
+        assert!(x.validate().is_err());
+    }
 }
@@ -1,25 +1,52 @@
+mod activation;
 mod annotator;
+mod channel_order;
 mod dataloader;
 mod device;
 mod dynconf;
 mod engine;
+pub mod export;
+mod graph_opt_level;
+mod input_range;
 mod logits_sampler;
+mod mask_format;
+mod mask_smooth;
 mod metric;
 mod min_opt_max;
+mod nms_method;
+mod obb_angle;
 pub mod onnx;
 pub mod ops;
 mod options;
+mod postprocessor;
+mod resize_mode;
+mod tiles;
 mod tokenizer_stream;
+mod tracker;
 mod ts;
+mod video;
 
+pub use activation::Activation;
 pub use annotator::Annotator;
+pub use channel_order::ChannelOrder;
 pub use dataloader::DataLoader;
 pub use device::Device;
 pub use dynconf::DynConf;
 pub use engine::OrtEngine;
+pub use graph_opt_level::GraphOptLevel;
+pub use input_range::InputRange;
 pub use logits_sampler::LogitsSampler;
+pub use mask_format::MaskFormat;
+pub use mask_smooth::MaskSmooth;
 pub use metric::Metric;
 pub use min_opt_max::MinOptMax;
+pub use nms_method::NmsMethod;
+pub use obb_angle::{AngleUnit, ObbAngle};
 pub use options::Options;
+pub use postprocessor::{DecodeCtx, PostProcessor};
+pub use resize_mode::ResizeMode;
+pub use tiles::{SeamMerge, Tile};
 pub use tokenizer_stream::TokenizerStream;
+pub use tracker::{Track, TrackState, Tracker};
 pub use ts::Ts;
+pub use video::{VideoReader, VideoWriter};
@@ -0,0 +1,33 @@
+/// How NMS treats boxes overlapping a kept one
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NmsMethod {
+    #[default]
+    Hard, // removed above the IoU threshold
+    Linear, // Soft-NMS, confidence scaled by (1 - IoU) above the IoU threshold
+    Gaussian {
+        sigma: f32, // Soft-NMS, confidence scaled by exp(-IoU^2 / sigma), threshold unused
+    },
+}
+
+impl NmsMethod {
+    /// Confidence factor of a box overlapping a kept one by `iou`
+    pub fn decay(&self, iou: f32, iou_threshold: f32) -> f32 {
+        match self {
+            Self::Hard => {
+                if iou > iou_threshold {
+                    0.
+                } else {
+                    1.
+                }
+            }
+            Self::Linear => {
+                if iou > iou_threshold {
+                    1. - iou
+                } else {
+                    1.
+                }
+            }
+            Self::Gaussian { sigma } => (-iou * iou / sigma.max(f32::EPSILON)).exp(),
+        }
+    }
+}
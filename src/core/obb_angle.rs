@@ -0,0 +1,112 @@
+use std::f32::consts::PI;
+
+/// Unit of the raw OBB angle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleUnit {
+    #[default]
+    Radians,
+    Degrees,
+}
+
+/// Angle convention of an OBB export, normalized to radians, clockwise (image coords), `[0, pi)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObbAngle {
+    pub unit: AngleUnit,
+    pub clockwise: bool,
+    pub min: f32, // lower bound of the raw range, in radians, the range spans pi
+}
+
+impl Default for ObbAngle {
+    fn default() -> Self {
+        // ultralytics: radians, [-pi/4, 3pi/4)
+        Self {
+            unit: AngleUnit::Radians,
+            clockwise: true,
+            min: -PI / 4.,
+        }
+    }
+}
+
+impl ObbAngle {
+    pub fn with_unit(mut self, x: AngleUnit) -> Self {
+        self.unit = x;
+        self
+    }
+
+    pub fn with_clockwise(mut self, x: bool) -> Self {
+        self.clockwise = x;
+        self
+    }
+
+    pub fn with_min(mut self, x: f32) -> Self {
+        self.min = x;
+        self
+    }
+
+    /// Raw angle -> canonical radians
+    pub fn to_canonical(&self, x: f32) -> f32 {
+        let x = match self.unit {
+            AngleUnit::Radians => x,
+            AngleUnit::Degrees => x.to_radians(),
+        };
+        let x = if self.clockwise { x } else { -x };
+        x.rem_euclid(PI)
+    }
+
+    /// Canonical radians -> raw angle
+    pub fn from_canonical(&self, x: f32) -> f32 {
+        let x = if self.clockwise { x } else { -x };
+        let x = (x - self.min).rem_euclid(PI) + self.min;
+        match self.unit {
+            AngleUnit::Radians => x,
+            AngleUnit::Degrees => x.to_degrees(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests_obb_angle {
+    use super::{AngleUnit, ObbAngle};
+    use std::f32::consts::PI;
+
+    fn round_trip(convention: ObbAngle, raws: &[f32]) {
+        for &raw in raws {
+            let canonical = convention.to_canonical(raw);
+            assert!((0. ..PI).contains(&canonical));
+            let raw_ = convention.from_canonical(canonical);
+            assert!((raw - raw_).abs() < 1e-3, "{convention:?}: {raw} != {raw_}");
+        }
+    }
+
+    #[test]
+    fn radians_ultralytics() {
+        round_trip(
+            ObbAngle::default(),
+            &[-PI / 4., -0.3, 0., 1.2, 3. * PI / 4. - 1e-2],
+        );
+        assert!((ObbAngle::default().to_canonical(-PI / 4.) - 3. * PI / 4.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn radians_zero_pi() {
+        let convention = ObbAngle::default().with_min(0.);
+        round_trip(convention, &[0., 0.5, PI / 2., PI - 1e-2]);
+        assert_eq!(convention.to_canonical(0.5), 0.5);
+    }
+
+    #[test]
+    fn degrees() {
+        let convention = ObbAngle::default()
+            .with_unit(AngleUnit::Degrees)
+            .with_min(-90f32.to_radians());
+        round_trip(convention, &[-90., -45., 0., 30., 89.]);
+        assert!((convention.to_canonical(90.) - PI / 2.).abs() < 1e-5);
+    }
+
+    #[test]
+    fn counterclockwise() {
+        let convention = ObbAngle::default().with_clockwise(false).with_min(0.);
+        round_trip(convention, &[0.1, 1., PI / 2., 3.]);
+        assert!((convention.to_canonical(0.5) - (PI - 0.5)).abs() < 1e-5);
+    }
+}
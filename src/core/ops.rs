@@ -1,7 +1,40 @@
 use anyhow::Result;
 use fast_image_resize as fr;
-use image::{DynamicImage, GenericImageView, ImageBuffer};
-use ndarray::{s, Array, Axis, IxDyn};
+use image::{DynamicImage, GenericImageView, GrayImage, ImageBuffer, Rgba, RgbaImage};
+use ndarray::{s, Array, ArrayView, ArrayViewMut, Axis, Ix3, IxDyn};
+use std::path::{Path, PathBuf};
+
+use crate::{DataLoader, Mbr, ResizeMode};
+
+/// Per-image transform applied by `letterbox`: x_input = x_original * ratio + pad
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub ratio: f32,
+    pub pad_x: f32,
+    pub pad_y: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self {
+            ratio: 1.,
+            pad_x: 0.,
+            pad_y: 0.,
+        }
+    }
+}
+
+impl Transform {
+    /// Map a point in model input back to original image
+    pub fn descale_xy(&self, x: f32, y: f32) -> (f32, f32) {
+        ((x - self.pad_x) / self.ratio, (y - self.pad_y) / self.ratio)
+    }
+
+    /// Map a length in model input back to original image
+    pub fn descale_len(&self, x: f32) -> f32 {
+        x / self.ratio
+    }
+}
 
 pub fn standardize(xs: Array<f32, IxDyn>, mean: &[f32], std: &[f32]) -> Array<f32, IxDyn> {
     let mean = Array::from_shape_vec((1, mean.len(), 1, 1), mean.to_vec()).unwrap();
@@ -27,6 +60,35 @@ pub fn scale_wh(w0: f32, h0: f32, w1: f32, h1: f32) -> (f32, f32, f32) {
     (r, (w0 * r).round(), (h0 * r).round())
 }
 
+pub fn check_image_size(x: &DynamicImage) -> Result<()> {
+    if x.width() == 0 || x.height() == 0 {
+        anyhow::bail!(
+            "Invalid image size: {}x{}, width and height must be non-zero",
+            x.width(),
+            x.height()
+        )
+    }
+    Ok(())
+}
+
+/// Reuse `ys` as a [n, 3, height, width] buffer, reallocated only when the shape changes
+fn fit_nchw(ys: &mut Array<f32, IxDyn>, n: usize, height: u32, width: u32) {
+    let shape = [n, 3, height as usize, width as usize];
+    if ys.shape() != shape {
+        *ys = Array::zeros(IxDyn(&shape));
+    }
+}
+
+/// Write an interleaved RGB buffer into a [3, height, width] view, with no temporaries
+fn assign_hwc(mut ys: ArrayViewMut<f32, Ix3>, buffer: &[u8], height: u32, width: u32) {
+    let xs = ArrayView::from_shape((height as usize, width as usize, 3), buffer)
+        .unwrap()
+        .permuted_axes([2, 0, 1]);
+    ndarray::Zip::from(&mut ys)
+        .and(&xs)
+        .for_each(|y, &x| *y = x as f32);
+}
+
 pub fn build_resizer(ty: &str) -> fr::Resizer {
     let ty = match ty {
         "box" => fr::FilterType::Box,
@@ -46,9 +108,24 @@ pub fn resize(
     width: u32,
     filter: &str,
 ) -> Result<Array<f32, IxDyn>> {
-    let mut ys = Array::ones((xs.len(), 3, height as usize, width as usize)).into_dyn();
+    let mut ys = Array::zeros(IxDyn(&[0]));
+    resize_into(xs, height, width, filter, &mut ys)?;
+    Ok(ys)
+}
+
+/// Same as `resize`, writing into `ys` which is only reallocated when its shape changes
+pub fn resize_into(
+    xs: &[DynamicImage],
+    height: u32,
+    width: u32,
+    filter: &str,
+    ys: &mut Array<f32, IxDyn>,
+) -> Result<()> {
+    fit_nchw(ys, xs.len(), height, width);
     let mut resizer = build_resizer(filter);
     for (idx, x) in xs.iter().enumerate() {
+        check_image_size(x)?;
+
         // src
         let src_image = fr::Image::from_vec_u8(
             std::num::NonZeroU32::new(x.width()).unwrap(),
@@ -69,17 +146,14 @@ pub fn resize(
         resizer
             .resize(&src_image.view(), &mut dst_image.view_mut())
             .unwrap();
-        let buffer = dst_image.into_vec();
-
-        // to ndarray
-        let y_ = Array::from_shape_vec((height as usize, width as usize, 3), buffer)
-            .unwrap()
-            .mapv(|x| x as f32)
-            .permuted_axes([2, 0, 1]);
-        let mut data = ys.slice_mut(s![idx, .., .., ..]);
-        data.assign(&y_);
+        assign_hwc(
+            ys.slice_mut(s![idx, .., .., ..]),
+            dst_image.buffer(),
+            height,
+            width,
+        );
     }
-    Ok(ys)
+    Ok(())
 }
 
 pub fn letterbox(
@@ -88,12 +162,33 @@ pub fn letterbox(
     width: u32,
     filter: &str,
     bg: Option<u8>,
-) -> Result<Array<f32, IxDyn>> {
-    let mut ys = Array::ones((xs.len(), 3, height as usize, width as usize)).into_dyn();
+) -> Result<(Array<f32, IxDyn>, Vec<Transform>)> {
+    let mut ys = Array::zeros(IxDyn(&[0]));
+    let transforms = letterbox_into(xs, height, width, filter, bg, &mut ys)?;
+    Ok((ys, transforms))
+}
+
+/// Same as `letterbox`, writing into `ys` which is only reallocated when its shape changes
+pub fn letterbox_into(
+    xs: &[DynamicImage],
+    height: u32,
+    width: u32,
+    filter: &str,
+    bg: Option<u8>,
+    ys: &mut Array<f32, IxDyn>,
+) -> Result<Vec<Transform>> {
+    fit_nchw(ys, xs.len(), height, width);
+    let mut transforms = Vec::with_capacity(xs.len());
     let mut resizer = build_resizer(filter);
     for (idx, x) in xs.iter().enumerate() {
+        check_image_size(x)?;
         let (w0, h0) = x.dimensions();
-        let (_, w_new, h_new) = scale_wh(w0 as f32, h0 as f32, width as f32, height as f32);
+        let (ratio, w_new, h_new) = scale_wh(w0 as f32, h0 as f32, width as f32, height as f32);
+        let (w_new, h_new) = (w_new.max(1.), h_new.max(1.)); // extreme aspect ratios
+        transforms.push(Transform {
+            ratio,
+            ..Default::default()
+        });
 
         // src
         let src_image = fr::Image::from_vec_u8(
@@ -133,17 +228,139 @@ pub fn letterbox(
 
         // resize
         resizer.resize(&src_image.view(), &mut dst_view).unwrap();
-        let buffer = dst_image.into_vec();
+        assign_hwc(
+            ys.slice_mut(s![idx, .., .., ..]),
+            dst_image.buffer(),
+            height,
+            width,
+        );
+    }
+    Ok(transforms)
+}
 
-        // to ndarray
-        let y_ = Array::from_shape_vec((height as usize, width as usize, 3), buffer)
-            .unwrap()
-            .mapv(|x| x as f32)
-            .permuted_axes([2, 0, 1]);
-        let mut data = ys.slice_mut(s![idx, .., .., ..]);
-        data.assign(&y_);
+/// Scale images by a fixed `scale` and center them on a (height, width) canvas
+pub fn canvas(
+    xs: &[DynamicImage],
+    height: u32,
+    width: u32,
+    scale: f32,
+    filter: &str,
+    bg: Option<u8>,
+) -> Result<(Array<f32, IxDyn>, Vec<Transform>)> {
+    let mut ys = Array::zeros(IxDyn(&[0]));
+    let transforms = canvas_into(xs, height, width, scale, filter, bg, &mut ys)?;
+    Ok((ys, transforms))
+}
+
+/// Same as `canvas`, writing into `ys` which is only reallocated when its shape changes
+pub fn canvas_into(
+    xs: &[DynamicImage],
+    height: u32,
+    width: u32,
+    scale: f32,
+    filter: &str,
+    bg: Option<u8>,
+    ys: &mut Array<f32, IxDyn>,
+) -> Result<Vec<Transform>> {
+    ResizeMode::Canvas { scale }.validate()?;
+    fit_nchw(ys, xs.len(), height, width);
+    let mut transforms = Vec::with_capacity(xs.len());
+    let mut resizer = build_resizer(filter);
+    for (idx, x) in xs.iter().enumerate() {
+        check_image_size(x)?;
+        let (w0, h0) = x.dimensions();
+        let w_new = (w0 as f32 * scale).round().max(1.) as u32;
+        let h_new = (h0 as f32 * scale).round().max(1.) as u32;
+        if w_new > width || h_new > height {
+            anyhow::bail!(
+                "Image {w0}x{h0} at scale {scale} does not fit in canvas {width}x{height}"
+            )
+        }
+        let (left, top) = ((width - w_new) / 2, (height - h_new) / 2);
+        transforms.push(Transform {
+            ratio: scale,
+            pad_x: left as f32,
+            pad_y: top as f32,
+        });
+
+        // src
+        let src_image = fr::Image::from_vec_u8(
+            std::num::NonZeroU32::new(w0).unwrap(),
+            std::num::NonZeroU32::new(h0).unwrap(),
+            x.to_rgb8().into_raw(),
+            fr::PixelType::U8x3,
+        )
+        .unwrap();
+
+        // dst
+        let mut dst_image = fr::Image::from_vec_u8(
+            std::num::NonZeroU32::new(width).unwrap(),
+            std::num::NonZeroU32::new(height).unwrap(),
+            vec![bg.unwrap_or(0); 3 * height as usize * width as usize],
+            src_image.pixel_type(),
+        )
+        .unwrap();
+
+        // mutable view
+        let mut dst_view = dst_image
+            .view_mut()
+            .crop(
+                left,
+                top,
+                std::num::NonZeroU32::new(w_new).unwrap(),
+                std::num::NonZeroU32::new(h_new).unwrap(),
+            )
+            .unwrap();
+
+        // resize
+        resizer.resize(&src_image.view(), &mut dst_view).unwrap();
+        assign_hwc(
+            ys.slice_mut(s![idx, .., .., ..]),
+            dst_image.buffer(),
+            height,
+            width,
+        );
     }
-    Ok(ys)
+    Ok(transforms)
+}
+
+/// Write letterboxed (height, width) versions of images to `out_dir` as PNGs, for eyeballing preprocessing
+pub fn dump_letterboxed<P: AsRef<Path>, Q: AsRef<Path>>(
+    paths: &[P],
+    height: u32,
+    width: u32,
+    out_dir: Q,
+) -> Result<Vec<PathBuf>> {
+    let out_dir = out_dir.as_ref();
+    std::fs::create_dir_all(out_dir)?;
+    let mut saveouts: Vec<PathBuf> = Vec::with_capacity(paths.len());
+    for path in paths.iter() {
+        let x = DataLoader::try_read(path)?;
+        let (xs, _) = letterbox(&[x], height, width, "catmullRom", Some(114))?;
+        let buffer = xs
+            .slice(s![0, .., .., ..])
+            .permuted_axes([1, 2, 0])
+            .iter()
+            .map(|x| x.round().clamp(0., 255.) as u8)
+            .collect::<Vec<_>>();
+        let img = image::RgbImage::from_raw(width, height, buffer).ok_or(anyhow::anyhow!(
+            "Failed to build image from letterboxed tensor"
+        ))?;
+
+        // same stems from different folders are kept apart
+        let stem = path
+            .as_ref()
+            .file_stem()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut saveout = out_dir.join(format!("{stem}.png"));
+        if saveouts.contains(&saveout) {
+            saveout = out_dir.join(format!("{stem}-{}.png", saveouts.len()));
+        }
+        img.save(&saveout)?;
+        saveouts.push(saveout);
+    }
+    Ok(saveouts)
 }
 
 pub fn resize_with_fixed_height(
@@ -156,6 +373,7 @@ pub fn resize_with_fixed_height(
     let mut ys = Array::ones((xs.len(), 3, height as usize, width as usize)).into_dyn();
     let mut resizer = build_resizer(filter);
     for (idx, x) in xs.iter().enumerate() {
+        check_image_size(x)?;
         let (w0, h0) = x.dimensions();
         let h_new = height;
         let w_new = height * w0 / h0;
@@ -225,6 +443,258 @@ pub fn descale_mask(mask: DynamicImage, w0: f32, h0: f32, w1: f32, h1: f32) -> D
     mask.resize_exact(w1 as u32, h1 as u32, image::imageops::FilterType::Triangle)
 }
 
+/// Like `descale_mask`, for inputs built with any `Transform` on a (height, width) input
+pub fn descale_mask_with(
+    mask: DynamicImage,
+    transform: &Transform,
+    width: f32,
+    height: f32,
+    w1: f32,
+    h1: f32,
+) -> DynamicImage {
+    let (sx, sy) = (mask.width() as f32 / width, mask.height() as f32 / height);
+    let x = (transform.pad_x * sx).round() as u32;
+    let y = (transform.pad_y * sy).round() as u32;
+    let w = (w1 * transform.ratio * sx).round().max(1.) as u32;
+    let h = (h1 * transform.ratio * sy).round().max(1.) as u32;
+    let mut mask = mask.to_owned();
+    let mask = mask.crop(x, y, w, h);
+    mask.resize_exact(w1 as u32, h1 as u32, image::imageops::FilterType::Triangle)
+}
+
+/// Mask pixels above this are foreground, i.e. a positive mask logit
+pub const MASK_THRESHOLD: u8 = 0;
+
+/// Binary 0/255 mask, foreground as per `MASK_THRESHOLD`, with specks, pinholes & jagged edges smoothed
+pub fn smooth_mask(mask: &GrayImage, open: u8, close: u8, sigma: f32) -> GrayImage {
+    let mut mask = mask.to_owned();
+    for p in mask.pixels_mut() {
+        p.0[0] = if p.0[0] > MASK_THRESHOLD { 255 } else { 0 };
+    }
+
+    // morphology: remove specks & fill pinholes
+    let norm = imageproc::distance_transform::Norm::LInf;
+    if open > 0 {
+        imageproc::morphology::open_mut(&mut mask, norm, open);
+    }
+    if close > 0 {
+        imageproc::morphology::close_mut(&mut mask, norm, close);
+    }
+
+    // gaussian: blur the edges, then binarize again at the midpoint of 0 & 255
+    if sigma > 0. {
+        mask = imageproc::filter::gaussian_blur_f32(&mask, sigma);
+        for p in mask.pixels_mut() {
+            p.0[0] = if p.0[0] > 127 { 255 } else { 0 };
+        }
+    }
+    mask
+}
+
+pub fn temperature_scale(p: f32, t: f32) -> f32 {
+    // sigmoid(logit(p) / t)
+    if t == 1. {
+        return p;
+    }
+    let p = p.clamp(1e-7, 1. - 1e-7);
+    let logit = (p / (1. - p)).ln();
+    1. / (1. + (-logit / t).exp())
+}
+
+/// Upright crop of an oriented box, sampled bilinearly with border clamping
+pub fn crop_rotated(img: &DynamicImage, mbr: &Mbr) -> Result<DynamicImage> {
+    check_image_size(img)?;
+    let vs = mbr.vertices();
+    if vs.len() < 4 {
+        anyhow::bail!("Mbr should have 4 vertices, got {}", vs.len());
+    }
+
+    // frame: origin at vs[2], u along vs[2] -> vs[1], v along vs[1] -> vs[0]
+    let (ux, uy) = ((vs[1].x - vs[2].x) as f32, (vs[1].y - vs[2].y) as f32);
+    let (vx, vy) = ((vs[0].x - vs[1].x) as f32, (vs[0].y - vs[1].y) as f32);
+    let (w, h) = ((ux * ux + uy * uy).sqrt(), (vx * vx + vy * vy).sqrt());
+    let (w_, h_) = (w.round() as u32, h.round() as u32);
+    if w_ == 0 || h_ == 0 {
+        anyhow::bail!("Empty rotated crop: {w}x{h}");
+    }
+    let (ux, uy, vx, vy) = (ux / w, uy / w, vx / h, vy / h);
+    let (x0, y0) = (vs[2].x as f32, vs[2].y as f32);
+
+    let src = img.to_rgba8();
+    let (sw, sh) = (src.width() as f32, src.height() as f32);
+    let sample = |x: f32, y: f32| -> Rgba<u8> {
+        let x = (x - 0.5).clamp(0., sw - 1.);
+        let y = (y - 0.5).clamp(0., sh - 1.);
+        let (x1, y1) = (x.floor(), y.floor());
+        let (x2, y2) = ((x1 + 1.).min(sw - 1.), (y1 + 1.).min(sh - 1.));
+        let (dx, dy) = (x - x1, y - y1);
+        let p = |x: f32, y: f32| src.get_pixel(x as u32, y as u32).0;
+        let (p11, p21, p12, p22) = (p(x1, y1), p(x2, y1), p(x1, y2), p(x2, y2));
+        let mut rgba = [0u8; 4];
+        for (c, v) in rgba.iter_mut().enumerate() {
+            let top = p11[c] as f32 * (1. - dx) + p21[c] as f32 * dx;
+            let bottom = p12[c] as f32 * (1. - dx) + p22[c] as f32 * dx;
+            *v = (top * (1. - dy) + bottom * dy).round() as u8;
+        }
+        Rgba(rgba)
+    };
+    let crop = RgbaImage::from_fn(w_, h_, |i, j| {
+        let (u, v) = (i as f32 + 0.5, j as f32 + 0.5);
+        sample(x0 + u * ux + v * vx, y0 + u * uy + v * vy)
+    });
+    Ok(DynamicImage::from(crop))
+}
+
 pub fn make_divisible(x: usize, divisor: usize) -> usize {
     (x - 1 + divisor) / divisor * divisor
 }
+
+#[cfg(test)]
+mod tests_ops {
+    use super::{
+        canvas, crop_rotated, dump_letterboxed, letterbox, letterbox_into, smooth_mask,
+        temperature_scale,
+    };
+    use crate::Mbr;
+    use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
+
+    #[test]
+    fn dump_letterboxed_pngs() {
+        let dir = std::env::temp_dir().join(format!("usls-dump-{}", std::process::id()));
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        std::fs::create_dir_all(&a).unwrap();
+        std::fs::create_dir_all(&b).unwrap();
+        let src = RgbaImage::from_pixel(30, 10, Rgba([10, 20, 30, 255]));
+        let paths = [a.join("x.png"), b.join("x.png")];
+        for p in paths.iter() {
+            src.save(p).unwrap();
+        }
+        let out = dir.join("out");
+        let saveouts = dump_letterboxed(&paths, 16, 24, &out).unwrap();
+        assert_eq!(saveouts, vec![out.join("x.png"), out.join("x-1.png")]);
+        let img = image::open(&saveouts[1]).unwrap();
+        assert_eq!(img.dimensions(), (24, 16));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn letterbox_pad_value() {
+        let img = DynamicImage::from(RgbaImage::from_pixel(8, 4, Rgba([200, 0, 0, 255])));
+        for bg in [0u8, 114, 255] {
+            let (ys, _) =
+                letterbox(std::slice::from_ref(&img), 8, 8, "bilinear", Some(bg)).unwrap();
+            // bottom rows are padding
+            assert!(ys
+                .slice(ndarray::s![0, .., 4.., ..])
+                .iter()
+                .all(|&x| x == bg as f32));
+        }
+    }
+
+    #[test]
+    fn letterbox_into_reuse() {
+        let img = DynamicImage::from(RgbaImage::from_pixel(8, 4, Rgba([200, 0, 0, 255])));
+        let xs = std::slice::from_ref(&img);
+        let mut ys = ndarray::Array::zeros(ndarray::IxDyn(&[0]));
+        letterbox_into(xs, 8, 8, "bilinear", Some(114), &mut ys).unwrap();
+        let ptr = ys.as_ptr();
+        ys.fill(0.);
+        letterbox_into(xs, 8, 8, "bilinear", Some(114), &mut ys).unwrap();
+        assert_eq!(ys.as_ptr(), ptr);
+        assert_eq!(ys, letterbox(xs, 8, 8, "bilinear", Some(114)).unwrap().0);
+
+        // reallocated for another batch size
+        letterbox_into(&[img.clone(), img], 8, 8, "bilinear", Some(114), &mut ys).unwrap();
+        assert_eq!(ys.shape(), &[2, 3, 8, 8]);
+    }
+
+    #[test]
+    fn canvas_centered() {
+        let img = DynamicImage::from(RgbaImage::from_pixel(4, 2, Rgba([200, 0, 0, 255])));
+        let (ys, ts) = canvas(std::slice::from_ref(&img), 8, 8, 1., "bilinear", None).unwrap();
+        assert_eq!(ys.shape(), &[1, 3, 8, 8]);
+        assert_eq!((ts[0].pad_x, ts[0].pad_y, ts[0].ratio), (2., 3., 1.));
+        assert_eq!(ts[0].descale_xy(2., 3.), (0., 0.));
+        let (_, ts) = canvas(std::slice::from_ref(&img), 9, 12, 2., "bilinear", None).unwrap();
+        assert_eq!((ts[0].pad_x, ts[0].pad_y), (2., 2.));
+        assert_eq!(ts[0].descale_xy(10., 6.), (4., 2.));
+        for scale in [3., 0., -1., f32::NAN] {
+            assert!(canvas(std::slice::from_ref(&img), 8, 8, scale, "bilinear", None).is_err());
+        }
+    }
+
+    #[test]
+    fn smooth_mask_small() {
+        // faint 9x9 square with a pinhole, plus a 1px speck
+        let mut x = image::GrayImage::new(16, 16);
+        for (i, j) in (2..11).flat_map(|i| (2..11).map(move |j| (i, j))) {
+            x.put_pixel(i, j, image::Luma([40]));
+        }
+        x.put_pixel(6, 6, image::Luma([0]));
+        x.put_pixel(14, 14, image::Luma([200]));
+
+        // thresholding only: faint pixels are foreground, same as contours
+        let y = smooth_mask(&x, 0, 0, 0.);
+        assert_eq!(y.get_pixel(2, 2).0, [255]);
+        assert_eq!(y.get_pixel(6, 6).0, [0]);
+        assert_eq!(y.get_pixel(14, 14).0, [255]);
+        assert!(y.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+
+        // open drops the speck, close fills the pinhole, blur keeps the square
+        let y = smooth_mask(&x, 1, 1, 1.);
+        assert_eq!(y.get_pixel(6, 6).0, [255]);
+        assert_eq!(y.get_pixel(14, 14).0, [0]);
+        assert_eq!(y.get_pixel(3, 3).0, [255]);
+        assert_eq!(y.get_pixel(12, 12).0, [0]);
+        assert!(y.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+    }
+
+    #[test]
+    fn temperature() {
+        for p in [0., 0.1, 0.5, 0.9, 1.] {
+            assert_eq!(temperature_scale(p, 1.), p);
+        }
+        // t > 1 flattens toward 0.5, t < 1 sharpens
+        assert!((temperature_scale(0.5, 3.) - 0.5).abs() < 1e-6);
+        let (hi, lo) = (temperature_scale(0.9, 2.), temperature_scale(0.1, 2.));
+        assert!(0.5 < hi && hi < 0.9 && 0.1 < lo && lo < 0.5);
+        assert!((hi + lo - 1.).abs() < 1e-5);
+        assert!(temperature_scale(0.9, 0.5) > 0.9);
+        // extreme values are clamped, never 0, 1 or NaN
+        for p in [0., 1., -3., 7.] {
+            let x = temperature_scale(p, 2.);
+            assert!(x > 0. && x < 1., "{p} -> {x}");
+        }
+        assert!(temperature_scale(1., 2.) > temperature_scale(0.99, 2.));
+    }
+
+    #[test]
+    fn crop_rotated_upright() {
+        let img = RgbaImage::from_fn(20, 10, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        let img = DynamicImage::from(img);
+        let mbr = Mbr::from_cxcywhr(10., 5., 8., 4., 0.);
+        let crop = crop_rotated(&img, &mbr).unwrap();
+        assert_eq!(crop.dimensions(), (8, 4));
+        assert_eq!(crop.get_pixel(0, 0), Rgba([6, 3, 0, 255]));
+        assert_eq!(crop.get_pixel(7, 3), Rgba([13, 6, 0, 255]));
+    }
+
+    #[test]
+    fn crop_rotated_quarter_turn() {
+        let img = RgbaImage::from_fn(20, 20, |x, y| Rgba([x as u8, y as u8, 0, 255]));
+        let img = DynamicImage::from(img);
+        let mbr = Mbr::from_cxcywhr(10., 10., 8., 4., std::f64::consts::FRAC_PI_2);
+        let crop = crop_rotated(&img, &mbr).unwrap();
+        assert_eq!(crop.dimensions(), (8, 4));
+        // u runs along +y, v along -x
+        let (a, b) = (crop.get_pixel(0, 0).0, crop.get_pixel(7, 0).0);
+        assert_eq!(a[0], b[0]);
+        assert!(b[1] > a[1]);
+    }
+
+    #[test]
+    fn crop_rotated_empty() {
+        let img = DynamicImage::new_rgb8(10, 10);
+        assert!(crop_rotated(&img, &Mbr::from_cxcywhr(5., 5., 0., 4., 0.)).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use anyhow::Result;
 
-use crate::{auto_load, models::YOLOTask, Device, MinOptMax};
+use crate::{
+    auto_load, models::YOLOTask, Activation, ChannelOrder, Device, GraphOptLevel, InputRange,
+    MaskFormat, MaskSmooth, MinOptMax, NmsMethod, ObbAngle, ResizeMode,
+};
 
 /// Options for building models
 #[derive(Debug, Clone)]
@@ -39,14 +42,34 @@ pub struct Options {
     pub trt_int8_enable: bool,
     pub trt_fp16_enable: bool,
 
+    // cuda related
+    pub deterministic: bool,
+
+    // session threads, None: onnxruntime defaults
+    pub intra_threads: Option<usize>,
+    pub inter_threads: Option<usize>,
+    pub graph_opt_level: GraphOptLevel,
+
     // options for Vision and Language models
     pub nc: Option<usize>,
     pub nk: Option<usize>,
     pub nm: Option<usize>,
     pub confs: Vec<f32>,
+    pub confs_file: Option<String>, // per-class `name threshold` lines, over `confs`
     pub kconfs: Vec<f32>,
+    pub kpt_temperatures: Vec<f32>, // per-keypoint calibration before `kconfs`
     pub iou: f32,
     pub apply_nms: bool,
+    pub final_conf: Option<f32>,        // after NMS & any rescoring
+    pub full_scores: bool,              // all classes' scores per bbox, memory-heavy
+    pub dedup: Option<(f32, f32)>, // (iou, confidence delta), duplicates removal for NMS-free outputs
+    pub nms_exempt_classes: Vec<usize>, // always kept by NMS
+    pub classes: Option<Vec<usize>>, // only these class ids are decoded, None: all
+    pub min_box_area: Option<f32>, // in original image pixels, before NMS
+    pub max_box_area: Option<f32>,
+    pub nms_exempt_suppress: bool, // exempt boxes may still suppress other classes
+    pub class_agnostic_nms: bool,  // false: boxes only suppress boxes of the same class
+    pub nms_method: NmsMethod,
     pub tokenizer: Option<String>,
     pub vocab: Option<String>,
     pub names: Option<Vec<String>>,  // names
@@ -58,7 +81,29 @@ pub struct Options {
     pub yolo_task: Option<YOLOTask>,
     pub anchors_first: bool, // yolo model output format like: [batch_size, anchors, xywh_clss_xxx]
     pub conf_independent: bool, // xywh_conf_clss
+    pub classify_activation: Activation,
+    #[deprecated(note = "use `classify_activation`, `true` is `Activation::Softmax`")]
     pub apply_probs_softmax: bool,
+    pub multilabel: bool, // sigmoid & per-class threshold for classification
+    pub mask_smooth: Option<MaskSmooth>, // None: keep exact mask areas
+    pub mask_format: MaskFormat,
+    pub skip_invalid_images: bool, // empty `Y` for zero-sized or undecodable images instead of an error
+    pub obb_angle: ObbAngle,
+    pub single_best: bool, // keep only the most confident box per image, no NMS
+    pub conf_inclusive: bool, // true: keep scores == threshold (default), false: strictly above like ultralytics
+    pub resize_mode: ResizeMode,
+    pub pad_value: u8, // letterbox & canvas background, 114 as YOLO trainings
+    pub input_range: InputRange, // after letterbox, default [0, 1] as YOLO models expect
+    pub channel_order: ChannelOrder, // of model inputs, RGB by default
+    pub image_mean: Option<Vec<f32>>, // per-channel (x - mean) / std after `input_range`, in `channel_order`
+    pub image_std: Option<Vec<f32>>,
+    pub seed: Option<u64>, // consumed by `LogitsSampler` (Blip), annotator colors are a fixed palette
+    pub end2end: Option<bool>, // [num_dets, boxes, scores, classes] or [b, k, 6] with NMS in-graph, None: detect from outputs
+    pub decode_masks: bool,    // false: boxes only for segment models
+    pub output_indices: Option<(usize, Option<usize>)>, // (preds, protos), None: guess from outputs
+    pub protos_indices: Option<Vec<usize>>, // protos at multiple resolutions, picked per instance
+    pub anchors: Option<Vec<Vec<(f32, f32)>>>, // raw YOLOv5/v7 heads: anchors (w, h) in pixels per level
+    pub strides: Option<Vec<usize>>,           // with `anchors`, None: input height / grid height
 }
 
 impl Default for Options {
@@ -93,15 +138,31 @@ impl Default for Options {
             i34: None,
             i35: None,
             trt_engine_cache_enable: true,
+            deterministic: false,
+            intra_threads: None,
+            inter_threads: None,
+            graph_opt_level: GraphOptLevel::default(),
             trt_int8_enable: false,
             trt_fp16_enable: false,
             nc: None,
             nk: None,
             nm: None,
             confs: vec![0.4f32],
+            confs_file: None,
             kconfs: vec![0.5f32],
+            kpt_temperatures: vec![1.0f32],
             iou: 0.45f32,
             apply_nms: true,
+            dedup: None,
+            final_conf: None,
+            full_scores: false,
+            nms_exempt_classes: vec![],
+            classes: None,
+            min_box_area: None,
+            max_box_area: None,
+            nms_exempt_suppress: false,
+            class_agnostic_nms: true,
+            nms_method: NmsMethod::default(),
             tokenizer: None,
             vocab: None,
             names: None,
@@ -113,7 +174,29 @@ impl Default for Options {
             yolo_task: None,
             anchors_first: false,
             conf_independent: false,
+            classify_activation: Activation::None,
+            #[allow(deprecated)]
             apply_probs_softmax: false,
+            multilabel: false,
+            mask_smooth: None,
+            mask_format: MaskFormat::default(),
+            skip_invalid_images: false,
+            output_indices: None,
+            anchors: None,
+            strides: None,
+            protos_indices: None,
+            obb_angle: ObbAngle::default(),
+            decode_masks: true,
+            end2end: None,
+            seed: None,
+            input_range: InputRange::default(),
+            resize_mode: ResizeMode::default(),
+            pad_value: 114,
+            channel_order: ChannelOrder::default(),
+            image_mean: None,
+            image_std: None,
+            conf_inclusive: true,
+            single_best: false,
         }
     }
 }
@@ -154,6 +237,32 @@ impl Options {
         self
     }
 
+    /// Reproducible CUDA outputs: cuDNN default conv algorithms without benchmarking or extra
+    /// workspace, copies in the default stream. Convolutions may be noticeably slower, and
+    /// TensorRT tactics are still chosen per machine.
+    pub fn with_deterministic(mut self, x: bool) -> Self {
+        self.deterministic = x;
+        self
+    }
+
+    /// Threads used within an operator, e.g. to cap CPU usage on shared machines
+    pub fn with_intra_threads(mut self, x: usize) -> Self {
+        self.intra_threads = Some(x);
+        self
+    }
+
+    /// Threads running independent operators, also switches the session to parallel execution
+    pub fn with_inter_threads(mut self, x: usize) -> Self {
+        self.inter_threads = Some(x);
+        self
+    }
+
+    /// Lower levels load faster, e.g. `Disable` for cold starts, at some runtime cost
+    pub fn with_graph_opt_level(mut self, x: GraphOptLevel) -> Self {
+        self.graph_opt_level = x;
+        self
+    }
+
     pub fn with_yolo_task(mut self, x: YOLOTask) -> Self {
         self.yolo_task = Some(x);
         self
@@ -165,7 +274,123 @@ impl Options {
     }
 
     pub fn apply_probs_softmax(mut self, x: bool) -> Self {
-        self.apply_probs_softmax = x;
+        self.classify_activation = if x {
+            Activation::Softmax
+        } else {
+            Activation::None
+        };
+        self
+    }
+
+    pub fn with_classify_activation(mut self, x: Activation) -> Self {
+        self.classify_activation = x;
+        self
+    }
+
+    pub fn with_mask_smooth(mut self, x: MaskSmooth) -> Self {
+        self.mask_smooth = Some(x);
+        self
+    }
+
+    pub fn with_mask_format(mut self, x: MaskFormat) -> Self {
+        self.mask_format = x;
+        self
+    }
+
+    pub fn with_multilabel(mut self, x: bool) -> Self {
+        self.multilabel = x;
+        self
+    }
+
+    pub fn with_skip_invalid_images(mut self, x: bool) -> Self {
+        self.skip_invalid_images = x;
+        self
+    }
+
+    pub fn with_resize_mode(mut self, x: ResizeMode) -> Self {
+        self.resize_mode = x;
+        self
+    }
+
+    /// Channel order the model expects, e.g. BGR of OpenCV/Caffe conversions
+    pub fn with_channel_order(mut self, x: ChannelOrder) -> Self {
+        self.channel_order = x;
+        self
+    }
+
+    /// Per-channel mean subtracted after `input_range`, e.g. `[0.485, 0.456, 0.406]` of ImageNet
+    pub fn with_image_mean(mut self, x: &[f32]) -> Self {
+        self.image_mean = Some(x.to_vec());
+        self
+    }
+
+    /// Per-channel std divided by after `image_mean`, e.g. `[0.229, 0.224, 0.225]` of ImageNet
+    pub fn with_image_std(mut self, x: &[f32]) -> Self {
+        self.image_std = Some(x.to_vec());
+        self
+    }
+
+    /// Background of letterboxed & canvas inputs, 114 by default
+    pub fn with_pad_value(mut self, x: u8) -> Self {
+        self.pad_value = x;
+        self
+    }
+
+    /// Keep only the most confident box per image and skip NMS. Every anchor is still scanned,
+    /// but only boxes beating the best so far are re-scaled.
+    pub fn with_single_best(mut self, x: bool) -> Self {
+        self.single_best = x;
+        self
+    }
+
+    pub fn with_conf_inclusive(mut self, x: bool) -> Self {
+        self.conf_inclusive = x;
+        self
+    }
+
+    pub fn with_input_range(mut self, x: InputRange) -> Self {
+        self.input_range = x;
+        self
+    }
+
+    pub fn with_seed(mut self, x: u64) -> Self {
+        self.seed = Some(x);
+        self
+    }
+
+    pub fn with_end2end(mut self, x: bool) -> Self {
+        self.end2end = Some(x);
+        self
+    }
+
+    pub fn with_decode_masks(mut self, x: bool) -> Self {
+        self.decode_masks = x;
+        self
+    }
+
+    pub fn with_obb_angle_convention(mut self, x: ObbAngle) -> Self {
+        self.obb_angle = x;
+        self
+    }
+
+    pub fn with_output_indices(mut self, preds_idx: usize, protos_idx: Option<usize>) -> Self {
+        self.output_indices = Some((preds_idx, protos_idx));
+        self
+    }
+
+    pub fn with_protos_indices(mut self, x: &[usize]) -> Self {
+        self.protos_indices = Some(x.to_vec());
+        self
+    }
+
+    /// Decode raw (non-exported) YOLOv5/v7 heads, one list of anchors per output level
+    pub fn with_anchors(mut self, x: &[&[(f32, f32)]]) -> Self {
+        self.anchors = Some(x.iter().map(|x| x.to_vec()).collect());
+        self
+    }
+
+    pub fn with_strides(mut self, x: &[usize]) -> Self {
+        self.strides = Some(x.to_vec());
         self
     }
 
@@ -224,6 +449,65 @@ impl Options {
         self
     }
 
+    /// Attach all classes' scores to each bbox, see `Y::scores`
+    pub fn with_full_scores(mut self, x: bool) -> Self {
+        self.full_scores = x;
+        self
+    }
+
+    /// Drop detections below `x` once suppression and rescoring are done
+    pub fn with_final_conf(mut self, x: f32) -> Self {
+        self.final_conf = Some(x);
+        self
+    }
+
+    /// Drop boxes overlapping a more confident one by more than `iou` and with a confidence
+    /// within `conf_delta` of it. Only for NMS-free outputs (end-to-end YOLO, RT-DETR).
+    pub fn with_dedup(mut self, iou: f32, conf_delta: f32) -> Self {
+        self.dedup = Some((iou, conf_delta));
+        self
+    }
+
+    /// Keep only predictions whose class id is in `x`, filtered before NMS
+    pub fn with_classes(mut self, x: &[usize]) -> Self {
+        self.classes = Some(x.to_vec());
+        self
+    }
+
+    /// Drop boxes smaller than `x` pixels of the original image, before NMS
+    pub fn with_min_box_area(mut self, x: f32) -> Self {
+        self.min_box_area = Some(x);
+        self
+    }
+
+    /// Drop boxes larger than `x` pixels of the original image, before NMS
+    pub fn with_max_box_area(mut self, x: f32) -> Self {
+        self.max_box_area = Some(x);
+        self
+    }
+
+    pub fn with_nms_exempt_classes(mut self, x: &[usize]) -> Self {
+        self.nms_exempt_classes = x.to_vec();
+        self
+    }
+
+    /// Whether NMS suppresses across classes, true by default
+    pub fn with_class_agnostic_nms(mut self, x: bool) -> Self {
+        self.class_agnostic_nms = x;
+        self
+    }
+
+    /// Hard NMS by default, Soft-NMS decays confidences of bboxes instead, then `confs` apply again
+    pub fn with_nms_method(mut self, x: NmsMethod) -> Self {
+        self.nms_method = x;
+        self
+    }
+
+    pub fn with_nms_exempt_suppress(mut self, x: bool) -> Self {
+        self.nms_exempt_suppress = x;
+        self
+    }
+
     pub fn with_nc(mut self, nc: usize) -> Self {
         self.nc = Some(nc);
         self
@@ -244,6 +528,16 @@ impl Options {
         self
     }
 
+    pub fn with_confs_file(mut self, x: &str) -> Self {
+        self.confs_file = Some(x.to_string());
+        self
+    }
+
+    pub fn with_kpt_temperature(mut self, x: &[f32]) -> Self {
+        self.kpt_temperatures = x.to_vec();
+        self
+    }
+
     pub fn with_kconfs(mut self, kconfs: &[f32]) -> Self {
         self.kconfs = kconfs.to_vec();
         self
@@ -0,0 +1,29 @@
+use anyhow::Result;
+use image::DynamicImage;
+use ndarray::{Array, IxDyn};
+
+use crate::{models::YOLOTask, ops::Transform, DynConf, Y};
+
+/// Everything a decoder needs to know besides raw outputs
+#[derive(Debug)]
+pub struct DecodeCtx<'a> {
+    pub xs0: &'a [DynamicImage],     // original images
+    pub transforms: &'a [Transform], // per-image preprocessing transforms
+    pub height: u32,                 // model input height
+    pub width: u32,                  // model input width
+    pub task: &'a YOLOTask,
+    pub nc: usize,
+    pub nk: usize,
+    pub nm: usize,
+    pub confs: &'a DynConf,
+    pub kconfs: &'a DynConf,
+    pub iou: f32,
+    pub names: Option<&'a [String]>,
+    pub names_kpt: Option<&'a [String]>,
+    pub anchors_first: bool,
+}
+
+/// Custom decoding of model outputs into `Y`s, shareable across threads
+pub trait PostProcessor: std::fmt::Debug + Send + Sync {
+    fn decode(&self, outputs: &[Array<f32, IxDyn>], ctx: &DecodeCtx) -> Result<Vec<Y>>;
+}
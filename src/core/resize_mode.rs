@@ -0,0 +1,21 @@
+/// How images are fitted into the model input
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ResizeMode {
+    #[default]
+    Letterbox, // fit within input, pad right & bottom
+    Canvas {
+        scale: f32, // fixed scale, centered in a larger input, never fitted
+    },
+}
+
+impl ResizeMode {
+    /// Error if the canvas scale is not finite and positive
+    pub fn validate(&self) -> anyhow::Result<()> {
+        match *self {
+            Self::Canvas { scale } if !(scale.is_finite() && scale > 0.) => {
+                anyhow::bail!("Canvas scale must be finite and positive, got {scale}")
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use crate::Bbox;
+
+/// A tile of a large image, in global pixel coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Tile {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Tile {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Overlapping `size` x `size` tiles covering the image, `overlap` in [0, 1) of `size`.
+    /// The last row and column are aligned to the image edges, images smaller than a tile
+    /// give a single tile.
+    pub fn grid(image_width: u32, image_height: u32, size: u32, overlap: f32) -> Vec<Tile> {
+        let size = size.max(1);
+        let step = ((size as f32 * (1. - overlap.clamp(0., 0.99))).round() as u32).max(1);
+        let starts = |len: u32| {
+            if len <= size {
+                return vec![0];
+            }
+            let mut xs = (0..len - size).step_by(step as usize).collect::<Vec<_>>();
+            xs.push(len - size);
+            xs
+        };
+        let (w, h) = (size.min(image_width), size.min(image_height));
+        let mut tiles = Vec::new();
+        for y in starts(image_height) {
+            for x in starts(image_width) {
+                tiles.push(Tile::new(x, y, w, h));
+            }
+        }
+        tiles
+    }
+
+    /// Whether a global bbox is cut by an edge shared with a neighbor tile (image edges excluded)
+    pub fn is_truncated(
+        &self,
+        bbox: &Bbox,
+        image_width: u32,
+        image_height: u32,
+        margin: f32,
+    ) -> bool {
+        let (x1, y1) = (self.x as f32, self.y as f32);
+        let (x2, y2) = ((self.x + self.width) as f32, (self.y + self.height) as f32);
+        (self.x > 0 && bbox.xmin() <= x1 + margin)
+            || (self.y > 0 && bbox.ymin() <= y1 + margin)
+            || (self.x + self.width < image_width && bbox.xmax() >= x2 - margin)
+            || (self.y + self.height < image_height && bbox.ymax() >= y2 - margin)
+    }
+}
+
+/// Cross-tile merging of detections around tile seams
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeamMerge {
+    pub margin: f32, // distance to an interior tile edge to be considered truncated
+    pub iou: f32,    // intersection over the smaller box, truncated boxes are partial views
+}
+
+impl Default for SeamMerge {
+    fn default() -> Self {
+        Self {
+            margin: 2.,
+            iou: 0.5,
+        }
+    }
+}
+
+impl SeamMerge {
+    pub fn with_margin(mut self, x: f32) -> Self {
+        self.margin = x;
+        self
+    }
+
+    pub fn with_iou(mut self, x: f32) -> Self {
+        self.iou = x;
+        self
+    }
+
+    /// Merge per-tile bboxes (already in global coordinates)
+    ///
+    /// A truncated bbox is dropped when a more complete one of the same class exists in another
+    /// tile, two truncated halves of the same object are fused into their union.
+    pub fn merge(
+        &self,
+        tiles: &[(Tile, Vec<Bbox>)],
+        image_width: u32,
+        image_height: u32,
+    ) -> Vec<Bbox> {
+        let mut xs: Vec<(usize, bool, &Bbox)> = Vec::new();
+        for (i, (tile, bboxes)) in tiles.iter().enumerate() {
+            for bbox in bboxes.iter() {
+                let truncated = tile.is_truncated(bbox, image_width, image_height, self.margin);
+                xs.push((i, truncated, bbox));
+            }
+        }
+
+        // complete first, and then by confidence
+        xs.sort_by(|a, b| {
+            a.1.cmp(&b.1).then_with(|| {
+                b.2.confidence()
+                    .partial_cmp(&a.2.confidence())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+        });
+
+        let mut kept: Vec<(usize, bool, Bbox)> = Vec::new();
+        for (i, truncated, bbox) in xs {
+            let duplicate = kept.iter_mut().find(|(j, _, x)| {
+                *j != i
+                    && x.id() == bbox.id()
+                    && x.intersect(bbox) / x.area().min(bbox.area()).max(f32::EPSILON) >= self.iou
+            });
+            match duplicate {
+                None => kept.push((i, truncated, bbox.clone())),
+                Some((_, true, x)) if truncated => {
+                    let confidence = x.confidence().max(bbox.confidence());
+                    *x = x
+                        .clone()
+                        .with_xyxy(
+                            x.xmin().min(bbox.xmin()),
+                            x.ymin().min(bbox.ymin()),
+                            x.xmax().max(bbox.xmax()),
+                            x.ymax().max(bbox.ymax()),
+                        )
+                        .with_confidence(confidence);
+                }
+                Some(_) => {} // covered by a more complete one
+            }
+        }
+        kept.into_iter().map(|(_, _, x)| x).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests_tiles {
+    use super::{SeamMerge, Tile};
+    use crate::Bbox;
+
+    fn bbox(x1: f32, y1: f32, x2: f32, y2: f32, confidence: f32) -> Bbox {
+        Bbox::default()
+            .with_xyxy(x1, y1, x2, y2)
+            .with_id(0)
+            .with_confidence(confidence)
+    }
+
+    #[test]
+    fn grid() {
+        let tiles = Tile::grid(100, 50, 40, 0.25);
+        let xs = tiles.iter().map(|t| t.x).filter(|&x| x > 0).count();
+        assert_eq!(tiles.len(), 3 * 2);
+        assert_eq!(xs, 2 * 2);
+        assert_eq!(tiles[1], Tile::new(30, 0, 40, 40));
+        assert_eq!(tiles[2], Tile::new(60, 0, 40, 40)); // aligned to the right edge
+        assert_eq!(tiles[5], Tile::new(60, 10, 40, 40));
+        assert_eq!(Tile::grid(30, 20, 40, 0.2), vec![Tile::new(0, 0, 30, 20)]);
+        assert_eq!(Tile::grid(80, 40, 40, 0.).len(), 2);
+    }
+
+    #[test]
+    fn truncated() {
+        let left = Tile::new(0, 0, 60, 100);
+        let right = Tile::new(40, 0, 60, 100);
+        assert!(left.is_truncated(&bbox(50., 10., 60., 20., 0.9), 100, 100, 1.));
+        assert!(!left.is_truncated(&bbox(0., 10., 20., 20., 0.9), 100, 100, 1.)); // image edge
+        assert!(right.is_truncated(&bbox(40., 10., 50., 20., 0.9), 100, 100, 1.));
+        assert!(!right.is_truncated(&bbox(80., 10., 100., 20., 0.9), 100, 100, 1.));
+    }
+
+    #[test]
+    fn prefer_complete() {
+        let (left, right) = (Tile::new(0, 0, 60, 100), Tile::new(40, 0, 60, 100));
+        let ys = SeamMerge::default().merge(
+            &[
+                (left, vec![bbox(45., 10., 60., 30., 0.95)]), // cut at x = 60
+                (right, vec![bbox(45., 10., 70., 30., 0.8)]), // complete
+            ],
+            100,
+            100,
+        );
+        assert_eq!(ys, vec![bbox(45., 10., 70., 30., 0.8)]);
+    }
+
+    #[test]
+    fn fuse_truncated_halves() {
+        let (left, right) = (Tile::new(0, 0, 60, 100), Tile::new(40, 0, 60, 100));
+        let ys = SeamMerge::default().merge(
+            &[
+                (left, vec![bbox(30., 10., 60., 30., 0.7)]),
+                (right, vec![bbox(40., 10., 75., 30., 0.9)]),
+            ],
+            100,
+            100,
+        );
+        assert_eq!(ys, vec![bbox(30., 10., 75., 30., 0.9)]);
+    }
+
+    #[test]
+    fn keep_distinct() {
+        let (left, right) = (Tile::new(0, 0, 60, 100), Tile::new(40, 0, 60, 100));
+        let ys = SeamMerge::default().merge(
+            &[
+                (
+                    left,
+                    vec![bbox(5., 10., 20., 30., 0.7), bbox(8., 12., 22., 32., 0.6)],
+                ),
+                (right, vec![bbox(80., 10., 95., 30., 0.9)]),
+            ],
+            100,
+            100,
+        );
+        assert_eq!(ys.len(), 3);
+    }
+}
@@ -0,0 +1,438 @@
+use crate::{Bbox, Y};
+
+const STD_POSITION: f32 = 1. / 20.;
+const STD_VELOCITY: f32 = 1. / 160.;
+
+/// Constant-velocity Kalman filter of one coordinate: position, velocity and their covariance
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Kalman1 {
+    x: f32,
+    v: f32,
+    p: [f32; 3], // [[p0, p1], [p1, p2]]
+}
+
+impl Kalman1 {
+    fn new(x: f32, std_x: f32, std_v: f32) -> Self {
+        Self {
+            x,
+            v: 0.,
+            p: [std_x.powi(2), 0., std_v.powi(2)],
+        }
+    }
+
+    fn predict(&mut self, std_x: f32, std_v: f32) {
+        let [p0, p1, p2] = self.p;
+        self.x += self.v;
+        self.p = [
+            p0 + 2. * p1 + p2 + std_x.powi(2),
+            p1 + p2,
+            p2 + std_v.powi(2),
+        ];
+    }
+
+    fn update(&mut self, z: f32, std_z: f32) {
+        let [p0, p1, p2] = self.p;
+        let s = p0 + std_z.powi(2);
+        let (k0, k1) = (p0 / s, p1 / s);
+        let innovation = z - self.x;
+        self.x += k0 * innovation;
+        self.v += k1 * innovation;
+        self.p = [(1. - k0) * p0, (1. - k0) * p1, p2 - k1 * p1];
+    }
+}
+
+/// Kalman filter on `cx, cy, aspect ratio, height` of a box, as in ByteTrack.
+/// Noises are diagonal, so each coordinate is an independent 2-state filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct KalmanBox([Kalman1; 4]);
+
+impl KalmanBox {
+    fn xyah(bbox: &Bbox) -> [f32; 4] {
+        [
+            bbox.cx(),
+            bbox.cy(),
+            bbox.width() / bbox.height().max(f32::EPSILON),
+            bbox.height(),
+        ]
+    }
+
+    /// Position and velocity stds, relative to the height except for the aspect ratio
+    fn stds(h: f32, position: f32, velocity: f32) -> [(f32, f32); 4] {
+        let (x, v) = (position * h, velocity * h);
+        [(x, v), (x, v), (1e-2, 1e-5), (x, v)]
+    }
+
+    fn new(bbox: &Bbox) -> Self {
+        let z = Self::xyah(bbox);
+        let stds = Self::stds(z[3], 2. * STD_POSITION, 10. * STD_VELOCITY);
+        Self(std::array::from_fn(|i| {
+            Kalman1::new(z[i], stds[i].0, stds[i].1)
+        }))
+    }
+
+    fn predict(&mut self) {
+        let stds = Self::stds(self.0[3].x, STD_POSITION, STD_VELOCITY);
+        for (x, (std_x, std_v)) in self.0.iter_mut().zip(stds) {
+            x.predict(std_x, std_v);
+        }
+    }
+
+    fn update(&mut self, bbox: &Bbox) {
+        let z = Self::xyah(bbox);
+        let mut stds = Self::stds(self.0[3].x, STD_POSITION, 0.);
+        stds[2].0 = 1e-1;
+        for ((x, z), (std_z, _)) in self.0.iter_mut().zip(z).zip(stds) {
+            x.update(z, std_z);
+        }
+    }
+
+    fn bbox(&self) -> Bbox {
+        let [cx, cy, a, h] = self.0.map(|x| x.x);
+        let w = a * h;
+        Bbox::default().with_xywh(cx - w / 2., cy - h / 2., w, h)
+    }
+}
+
+/// Lifecycle of a track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackState {
+    Tentative, // born from a single detection, removed if not matched in the next frame
+    Tracked,
+    Lost, // not matched recently, kept for `max_lost` frames to be recovered
+}
+
+/// An object followed across frames
+#[derive(Debug, Clone)]
+pub struct Track {
+    id: usize,
+    bbox: Bbox,
+    state: TrackState,
+    age: usize,       // frames since birth
+    hits: usize,      // matched detections
+    time_lost: usize, // frames since the last match
+    kalman: KalmanBox,
+}
+
+impl Track {
+    fn new(id: usize, bbox: &Bbox, state: TrackState) -> Self {
+        Self {
+            id,
+            bbox: bbox.clone(),
+            state,
+            age: 0,
+            hits: 1,
+            time_lost: 0,
+            kalman: KalmanBox::new(bbox),
+        }
+    }
+
+    fn predict(&mut self) {
+        self.kalman.predict();
+        self.age += 1;
+        self.time_lost += 1;
+    }
+
+    fn update(&mut self, bbox: &Bbox) {
+        self.kalman.update(bbox);
+        let b = self.kalman.bbox();
+        self.bbox = bbox
+            .clone()
+            .with_xywh(b.xmin(), b.ymin(), b.width(), b.height());
+        self.state = TrackState::Tracked;
+        self.hits += 1;
+        self.time_lost = 0;
+    }
+
+    /// Box predicted by the motion model
+    fn predicted(&self) -> Bbox {
+        self.kalman.bbox()
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Latest smoothed box, carrying class, name and confidence of its detection
+    pub fn bbox(&self) -> &Bbox {
+        &self.bbox
+    }
+
+    pub fn state(&self) -> TrackState {
+        self.state
+    }
+
+    pub fn age(&self) -> usize {
+        self.age
+    }
+
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    pub fn time_lost(&self) -> usize {
+        self.time_lost
+    }
+}
+
+/// ByteTrack multi-object tracker over per-frame bboxes
+///
+/// High-confidence detections are matched to all tracks first, low-confidence ones then recover
+/// the remaining tracked objects (e.g. occluded ones). Matching is class-agnostic and greedy on IoU.
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    high: f32,
+    low: f32,
+    new_track: f32,
+    match_iou: f32,
+    low_match_iou: f32,
+    max_lost: usize,
+    frame: usize,
+    next_id: usize,
+    tracks: Vec<Track>,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Self {
+            high: 0.5,
+            low: 0.1,
+            new_track: 0.6,
+            match_iou: 0.2,
+            low_match_iou: 0.5,
+            max_lost: 30,
+            frame: 0,
+            next_id: 1,
+            tracks: Vec::new(),
+        }
+    }
+}
+
+impl Tracker {
+    /// Detections at or above go to the first association
+    pub fn with_high_thresh(mut self, x: f32) -> Self {
+        self.high = x;
+        self
+    }
+
+    /// Detections below are ignored
+    pub fn with_low_thresh(mut self, x: f32) -> Self {
+        self.low = x;
+        self
+    }
+
+    /// Unmatched detections at or above start new tracks
+    pub fn with_new_track_thresh(mut self, x: f32) -> Self {
+        self.new_track = x;
+        self
+    }
+
+    /// Minimum IoU to match high-confidence detections
+    pub fn with_match_iou(mut self, x: f32) -> Self {
+        self.match_iou = x;
+        self
+    }
+
+    /// Minimum IoU to match low-confidence detections
+    pub fn with_low_match_iou(mut self, x: f32) -> Self {
+        self.low_match_iou = x;
+        self
+    }
+
+    /// Frames a lost track is kept before removal, like ByteTrack's `track_buffer`
+    pub fn with_max_lost(mut self, x: usize) -> Self {
+        self.max_lost = x;
+        self
+    }
+
+    /// All live tracks, lost ones included
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    pub fn reset(&mut self) {
+        self.frame = 0;
+        self.next_id = 1;
+        self.tracks.clear();
+    }
+
+    /// Feed the bboxes of the next frame, returns the tracks matched in this frame
+    pub fn update(&mut self, y: &Y) -> Vec<Track> {
+        let bboxes = y.bboxes().map(|x| x.as_slice()).unwrap_or_default();
+        let (high, low): (Vec<&Bbox>, Vec<&Bbox>) = bboxes
+            .iter()
+            .filter(|x| x.confidence() >= self.low)
+            .partition(|x| x.confidence() >= self.high);
+        for track in self.tracks.iter_mut() {
+            track.predict();
+        }
+
+        // 1st: high-confidence detections vs confirmed tracks, lost ones included
+        let confirmed = self.indices(|x| x.state != TrackState::Tentative);
+        let (matches, high_left) = self.associate(&confirmed, &high, self.match_iou);
+        let matched = matches.iter().map(|&(t, _)| t).collect::<Vec<_>>();
+        for (t, d) in matches {
+            self.tracks[t].update(high[d]);
+        }
+
+        // 2nd: low-confidence detections vs tracks still unmatched
+        let tracked = confirmed
+            .into_iter()
+            .filter(|t| !matched.contains(t) && self.tracks[*t].state == TrackState::Tracked)
+            .collect::<Vec<_>>();
+        let (matches, _) = self.associate(&tracked, &low, self.low_match_iou);
+        for (t, d) in matches {
+            self.tracks[t].update(low[d]);
+        }
+
+        // 3rd: remaining high-confidence detections vs tentative tracks
+        let high = high_left.into_iter().map(|d| high[d]).collect::<Vec<_>>();
+        let tentative = self.indices(|x| x.state == TrackState::Tentative);
+        let (matches, high_left) = self.associate(&tentative, &high, self.low_match_iou);
+        for (t, d) in matches {
+            self.tracks[t].update(high[d]);
+        }
+
+        // lifecycle
+        let max_lost = self.max_lost;
+        self.tracks.retain_mut(|x| match (x.time_lost, x.state) {
+            (0, _) => true,
+            (_, TrackState::Tentative) => false,
+            (n, _) => {
+                x.state = TrackState::Lost;
+                n <= max_lost
+            }
+        });
+        let state = match self.frame {
+            0 => TrackState::Tracked,
+            _ => TrackState::Tentative,
+        };
+        for d in high_left {
+            if high[d].confidence() >= self.new_track {
+                self.tracks.push(Track::new(self.next_id, high[d], state));
+                self.next_id += 1;
+            }
+        }
+        self.frame += 1;
+
+        self.tracks
+            .iter()
+            .filter(|x| x.time_lost == 0 && x.state == TrackState::Tracked)
+            .cloned()
+            .collect()
+    }
+
+    fn indices(&self, f: impl Fn(&Track) -> bool) -> Vec<usize> {
+        (0..self.tracks.len())
+            .filter(|&i| f(&self.tracks[i]))
+            .collect()
+    }
+
+    /// Greedy matching by descending IoU, returns (track, detection) pairs and unmatched detections
+    fn associate(
+        &self,
+        tracks: &[usize],
+        bboxes: &[&Bbox],
+        iou: f32,
+    ) -> (Vec<(usize, usize)>, Vec<usize>) {
+        let mut pairs = Vec::new();
+        for &t in tracks.iter() {
+            let predicted = self.tracks[t].predicted();
+            for (d, bbox) in bboxes.iter().enumerate() {
+                let x = predicted.iou(bbox);
+                if x >= iou {
+                    pairs.push((x, t, d));
+                }
+            }
+        }
+        pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        let mut matches: Vec<(usize, usize)> = Vec::new();
+        for (_, t, d) in pairs {
+            if matches.iter().all(|&(t_, d_)| t_ != t && d_ != d) {
+                matches.push((t, d));
+            }
+        }
+        let unmatched = (0..bboxes.len())
+            .filter(|d| matches.iter().all(|&(_, d_)| d_ != *d))
+            .collect();
+        (matches, unmatched)
+    }
+}
+
+#[cfg(test)]
+mod tests_tracker {
+    use super::{TrackState, Tracker};
+    use crate::{Bbox, Y};
+
+    fn frame(xs: &[(f32, f32)]) -> Y {
+        let bboxes = xs
+            .iter()
+            .map(|&(x, confidence)| {
+                Bbox::default()
+                    .with_xywh(x, 10., 20., 40.)
+                    .with_id(0)
+                    .with_confidence(confidence)
+            })
+            .collect::<Vec<_>>();
+        Y::default().with_bboxes(&bboxes)
+    }
+
+    fn ids(xs: &[super::Track]) -> Vec<usize> {
+        xs.iter().map(|x| x.id()).collect()
+    }
+
+    #[test]
+    fn persistent_ids() {
+        let mut tracker = Tracker::default();
+        let tracks = tracker.update(&frame(&[(0., 0.9), (100., 0.8)]));
+        assert_eq!(ids(&tracks), vec![1, 2]);
+
+        // moving right, order of detections swapped
+        let tracks = tracker.update(&frame(&[(104., 0.85), (4., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1, 2]);
+        assert!((tracks[0].bbox().xmin() - 4.).abs() < 2.);
+        assert_eq!(tracks[0].age(), 1);
+
+        // low-confidence detection keeps the track alive (2nd association)
+        let tracks = tracker.update(&frame(&[(8., 0.3), (108., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1, 2]);
+        assert_eq!(tracks[0].hits(), 3);
+    }
+
+    #[test]
+    fn lost_and_recovered() {
+        let mut tracker = Tracker::default().with_max_lost(2);
+        tracker.update(&frame(&[(0., 0.9)]));
+        assert!(tracker.update(&frame(&[])).is_empty());
+        assert_eq!(tracker.tracks()[0].state(), TrackState::Lost);
+        let tracks = tracker.update(&frame(&[(0., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1]);
+
+        for _ in 0..3 {
+            tracker.update(&frame(&[]));
+        }
+        assert!(tracker.tracks().is_empty());
+    }
+
+    #[test]
+    fn tentative() {
+        let mut tracker = Tracker::default();
+        tracker.update(&frame(&[(0., 0.9)]));
+
+        // a new object is confirmed only when seen again
+        let tracks = tracker.update(&frame(&[(0., 0.9), (200., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1]);
+        let tracks = tracker.update(&frame(&[(0., 0.9), (200., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1, 2]);
+
+        // one-frame false positive is dropped, never gets an id reported
+        tracker.update(&frame(&[(0., 0.9), (200., 0.9), (400., 0.9)]));
+        let tracks = tracker.update(&frame(&[(0., 0.9), (200., 0.9)]));
+        assert_eq!(ids(&tracks), vec![1, 2]);
+        assert_eq!(tracker.tracks().len(), 2);
+
+        // below `new_track`, no track is started
+        let mut tracker = Tracker::default();
+        assert!(tracker.update(&frame(&[(0., 0.55)])).is_empty());
+        assert!(tracker.tracks().is_empty());
+    }
+}
@@ -0,0 +1,274 @@
+//! Video decoding & encoding through the `ffmpeg` and `ffprobe` executables on PATH
+
+use anyhow::{anyhow, bail, Result};
+use image::{DynamicImage, RgbImage, RgbaImage};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Parse `width,height,r_frame_rate` from ffprobe, e.g. `1920,1080,30000/1001`
+fn parse_probe(s: &str) -> Result<(u32, u32, f32)> {
+    let xs = s.trim().lines().next().unwrap_or_default();
+    let xs = xs.split(',').map(|x| x.trim()).collect::<Vec<_>>();
+    if xs.len() < 3 {
+        bail!("Unexpected ffprobe output: {s:?}");
+    }
+    let fps = match xs[2].split_once('/') {
+        Some((a, b)) => a.parse::<f32>()? / b.parse::<f32>()?,
+        None => xs[2].parse::<f32>()?,
+    };
+    if !fps.is_finite() || fps <= 0. {
+        bail!("Invalid frame rate: {:?}", xs[2]);
+    }
+    Ok((xs[0].parse()?, xs[1].parse()?, fps))
+}
+
+/// Frames of a video file as RGB images, in order, call `finish` to check for decoding errors
+pub struct VideoReader {
+    child: Child,
+    stdout: ChildStdout,
+    width: u32,
+    height: u32,
+    fps: f32,
+    error: Option<anyhow::Error>,
+}
+
+impl VideoReader {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            bail!("{path:?} Not Exists");
+        }
+        let probe = Command::new("ffprobe")
+            .args([
+                "-v",
+                "error",
+                "-select_streams",
+                "v:0",
+                "-show_entries",
+                "stream=width,height,r_frame_rate",
+                "-of",
+                "csv=p=0",
+            ])
+            .arg(path)
+            .output()
+            .map_err(|err| anyhow!("Failed to run ffprobe, is ffmpeg installed? {err}"))?;
+        if !probe.status.success() {
+            bail!(
+                "ffprobe failed on {path:?}: {}",
+                String::from_utf8_lossy(&probe.stderr)
+            );
+        }
+        let (width, height, fps) = parse_probe(&String::from_utf8_lossy(&probe.stdout))?;
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-v", "error", "-noautorotate", "-i"]) // keep the probed width & height
+            .arg(path)
+            .args(["-f", "rawvideo", "-pix_fmt", "rgb24", "-"]);
+        Self::spawn(cmd, width, height, fps)
+    }
+
+    /// Read raw rgb24 frames from the stdout of `cmd`
+    fn spawn(mut cmd: Command, width: u32, height: u32, fps: f32) -> Result<Self> {
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stdin(Stdio::null())
+            .spawn()
+            .map_err(|err| anyhow!("Failed to run ffmpeg: {err}"))?;
+        let stdout = child.stdout.take().ok_or(anyhow!("No ffmpeg stdout"))?;
+        Ok(Self {
+            child,
+            stdout,
+            width,
+            height,
+            fps,
+            error: None,
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+
+    /// Why iteration stopped early, if it did
+    pub fn error(&self) -> Option<&anyhow::Error> {
+        self.error.as_ref()
+    }
+
+    /// Error if decoding failed, e.g. ffmpeg died mid-stream
+    pub fn finish(mut self) -> Result<()> {
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Iterator for VideoReader {
+    type Item = DynamicImage;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.error.is_some() {
+            return None;
+        }
+        let mut buf = vec![0u8; (self.width * self.height * 3) as usize];
+        if let Err(err) = self.stdout.read_exact(&mut buf) {
+            // EOF is the end of stream only if ffmpeg exited cleanly
+            self.error = match err.kind() {
+                std::io::ErrorKind::UnexpectedEof => match self.child.wait() {
+                    Ok(status) if status.success() => None,
+                    Ok(status) => Some(anyhow!("ffmpeg exited with {status}")),
+                    Err(err) => Some(anyhow!("Failed to wait for ffmpeg: {err}")),
+                },
+                _ => Some(anyhow!("Failed to read frame: {err}")),
+            };
+            return None;
+        }
+        RgbImage::from_raw(self.width, self.height, buf).map(DynamicImage::from)
+    }
+}
+
+impl Drop for VideoReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// H.264 encoder of RGBA frames, call `finish` to close the file
+pub struct VideoWriter {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    width: u32,
+    height: u32,
+}
+
+impl VideoWriter {
+    pub fn create<P: AsRef<Path>>(path: P, width: u32, height: u32, fps: f32) -> Result<Self> {
+        if let Some(dir) = path.as_ref().parent() {
+            if !dir.as_os_str().is_empty() {
+                std::fs::create_dir_all(dir)?;
+            }
+        }
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-y", "-v", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-s", &format!("{width}x{height}"), "-r", &fps.to_string()])
+            .args(["-i", "-", "-c:v", "libx264", "-pix_fmt", "yuv420p"])
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2"]) // yuv420p needs even sizes
+            .arg(path.as_ref());
+        Self::spawn(cmd, width, height)
+    }
+
+    /// Write raw rgba frames to the stdin of `cmd`
+    fn spawn(mut cmd: Command, width: u32, height: u32) -> Result<Self> {
+        let mut child = cmd
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|err| anyhow!("Failed to run ffmpeg, is it installed? {err}"))?;
+        let stdin = child.stdin.take();
+        Ok(Self {
+            child,
+            stdin,
+            width,
+            height,
+        })
+    }
+
+    pub fn write(&mut self, frame: &RgbaImage) -> Result<()> {
+        if frame.dimensions() != (self.width, self.height) {
+            bail!(
+                "Frame size {:?} differs from video size {:?}",
+                frame.dimensions(),
+                (self.width, self.height)
+            );
+        }
+        match &mut self.stdin {
+            Some(stdin) => Ok(stdin.write_all(frame.as_raw())?),
+            None => bail!("Video writer is closed"),
+        }
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        drop(self.stdin.take());
+        let status = self.child.wait()?;
+        if !status.success() {
+            bail!("ffmpeg exited with {status}");
+        }
+        Ok(())
+    }
+}
+
+impl Drop for VideoWriter {
+    fn drop(&mut self) {
+        // close stdin so ffmpeg writes the trailer, then reap it
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests_video {
+    use super::{parse_probe, VideoReader, VideoWriter};
+    use std::process::Command;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", script]);
+        cmd
+    }
+
+    #[test]
+    fn probe() {
+        let (w, h, fps) = parse_probe("1920,1080,30000/1001\n").unwrap();
+        assert_eq!((w, h), (1920, 1080));
+        assert!((fps - 29.97).abs() < 1e-2);
+        assert_eq!(parse_probe("640,480,25").unwrap(), (640, 480, 25.));
+        assert!(parse_probe("640,480,0/0").is_err());
+        assert!(parse_probe("").is_err());
+        assert!(parse_probe("640,480").is_err());
+        assert!(parse_probe("a,480,25").is_err());
+        assert!(parse_probe("640,480,-25").is_err());
+        assert!(VideoReader::open("no-such-video.mp4").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reader_exit_status() {
+        // 1x1 rgb24: 3 bytes per frame
+        let mut reader = VideoReader::spawn(sh("printf abcdef"), 1, 1, 25.).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.error().is_none());
+        assert!(reader.finish().is_ok());
+
+        let mut reader = VideoReader::spawn(sh("printf abc; exit 3"), 1, 1, 25.).unwrap();
+        assert_eq!(reader.by_ref().count(), 1);
+        assert!(reader.next().is_none());
+        let err = reader.finish().unwrap_err();
+        assert!(err.to_string().contains("exited with"), "{err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writer_errors() {
+        let mut writer = VideoWriter::spawn(sh("cat > /dev/null"), 2, 2).unwrap();
+        assert!(writer.write(&image::RgbaImage::new(3, 2)).is_err());
+        assert!(writer.write(&image::RgbaImage::new(2, 2)).is_ok());
+        assert!(writer.finish().is_ok());
+
+        let writer = VideoWriter::spawn(sh("cat > /dev/null; exit 1"), 2, 2).unwrap();
+        assert!(writer.finish().is_err());
+
+        // dropped without `finish`: stdin is closed & the child reaped, no hang
+        let mut writer = VideoWriter::spawn(sh("cat > /dev/null"), 2, 2).unwrap();
+        writer.write(&image::RgbaImage::new(2, 2)).unwrap();
+        drop(writer);
+    }
+}
@@ -15,6 +15,7 @@ pub struct Blip {
     pub batch_visual: MinOptMax,
     pub batch_textual: MinOptMax,
     tokenizer: TokenizerStream,
+    seed: Option<u64>,
 }
 
 impl Blip {
@@ -39,6 +40,7 @@ impl Blip {
             height,
             width,
             tokenizer,
+            seed: options_textual.seed,
         })
     }
 
@@ -95,7 +97,10 @@ impl Blip {
             }
         };
 
-        let mut logits_sampler = LogitsSampler::new();
+        let mut logits_sampler = match self.seed {
+            Some(seed) => LogitsSampler::new().with_seed(seed),
+            None => LogitsSampler::new(),
+        };
         loop {
             let input_ids_nd: Array<f32, IxDyn> = Array::from_vec(input_ids.to_owned()).into_dyn();
             let input_ids_nd = input_ids_nd.insert_axis(Axis(0));
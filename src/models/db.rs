@@ -45,7 +45,7 @@ impl DB {
     }
 
     pub fn run(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
-        let xs_ = ops::letterbox(
+        let (xs_, _) = ops::letterbox(
             xs,
             self.height.opt as u32,
             self.width.opt as u32,
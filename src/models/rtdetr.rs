@@ -14,6 +14,7 @@ pub struct RTDETR {
     confs: DynConf,
     nc: usize,
     names: Option<Vec<String>>,
+    dedup: Option<(f32, f32)>,
 }
 
 impl RTDETR {
@@ -52,11 +53,12 @@ impl RTDETR {
             width,
             batch,
             names,
+            dedup: options.dedup,
         })
     }
 
     pub fn run(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
-        let xs_ = ops::letterbox(
+        let (xs_, _) = ops::letterbox(
             xs,
             self.height() as u32,
             self.width() as u32,
@@ -116,7 +118,11 @@ impl RTDETR {
                         .with_name(self.names.as_ref().map(|names| names[id].to_owned())),
                 )
             }
-            ys.push(Y::default().with_bboxes(&y_bboxes));
+            let mut y = Y::default().with_bboxes(&y_bboxes);
+            if let Some((iou, conf_delta)) = self.dedup {
+                y = y.apply_bboxes_dedup(iou, conf_delta);
+            }
+            ys.push(y);
         }
         Ok(ys)
     }
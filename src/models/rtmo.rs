@@ -39,7 +39,7 @@ impl RTMO {
     }
 
     pub fn run(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
-        let xs_ = ops::letterbox(
+        let (xs_, _) = ops::letterbox(
             xs,
             self.height() as u32,
             self.width() as u32,
@@ -103,7 +103,7 @@ impl RTMO {
                     let y = kpt[1] / ratio;
                     let c = kpt[2];
                     if c < self.kconfs[i] {
-                        kpts_.push(Keypoint::default());
+                        kpts_.push(Keypoint::default().with_id(i as isize)); // placeholder
                     } else {
                         kpts_.push(
                             Keypoint::default()
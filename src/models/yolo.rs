@@ -1,10 +1,18 @@
 use anyhow::Result;
 use clap::ValueEnum;
 use image::{DynamicImage, ImageBuffer};
-use ndarray::{s, Array, Axis, IxDyn};
+use ndarray::{s, Array, ArrayView, Axis, IxDyn};
+use ort::TensorElementType;
 use regex::Regex;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use crate::{ops, Bbox, DynConf, Keypoint, Mbr, MinOptMax, Options, OrtEngine, Polygon, Prob, Y};
+use crate::{
+    ops::{self, Transform},
+    Activation, Annotator, Bbox, ChannelOrder, DataLoader, DecodeCtx, DynConf, InputRange,
+    Keypoint, Mask, MaskFormat, MaskSmooth, Mbr, MinOptMax, NmsMethod, ObbAngle, Options,
+    OrtEngine, Polygon, PostProcessor, Prob, ResizeMode, Rle, SeamMerge, Tile, SAFE_CROSS_MARK, Y,
+};
 
 const CXYWH_OFFSET: usize = 4;
 const KPT_STEP: usize = 3;
@@ -30,17 +38,51 @@ pub struct YOLO {
     task: YOLOTask,
     confs: DynConf,
     kconfs: DynConf,
+    kpt_temperatures: DynConf,
     iou: f32,
-    names: Option<Vec<String>>,
+    names: Option<Arc<Vec<String>>>, // shared with every `Prob`
     names_kpt: Option<Vec<String>>,
     apply_nms: bool,
+    dedup: Option<(f32, f32)>,
+    final_conf: Option<f32>,
+    full_scores: bool,
+    nms_exempt_classes: Vec<usize>,
+    nms_exempt_suppress: bool,
+    class_agnostic_nms: bool,
+    nms_method: NmsMethod,
+    classes: Option<Vec<usize>>,
+    min_box_area: Option<f32>,
+    max_box_area: Option<f32>,
     anchors_first: bool,
     conf_independent: bool,
-    apply_probs_softmax: bool,
+    classify_activation: Activation,
+    multilabel: bool,
+    skip_invalid_images: bool,
+    preds_idx: usize,
+    protos_idxs: Vec<usize>, // multiple protos resolutions are allowed
+    levels_idxs: Vec<usize>, // raw YOLOv5/v7 heads, decoded with `anchors`
+    anchors: Vec<Vec<(f32, f32)>>,
+    strides: Option<Vec<usize>>,
+    mask_smooth: Option<MaskSmooth>,
+    mask_format: MaskFormat,
+    obb_angle: ObbAngle,
+    decode_masks: bool,
+    end2end: bool,
+    input_range: InputRange,
+    channel_order: ChannelOrder,
+    image_stats: Option<(Vec<f32>, Vec<f32>)>, // (mean, std)
+    resize_mode: ResizeMode,
+    pad_value: u8,
+    conf_inclusive: bool,
+    single_best: bool,
+    processor: Option<Box<dyn PostProcessor>>, // None: built-in decoding
+    annotator: Option<Annotator>,
+    input: Array<f32, IxDyn>, // reused across `run`s
 }
 
 impl YOLO {
     pub fn new(options: Options) -> Result<Self> {
+        options.resize_mode.validate()?;
         let mut engine = OrtEngine::new(&options)?;
         let (batch, height, width) = (
             engine.batch().to_owned(),
@@ -60,7 +102,7 @@ impl YOLO {
                 "pose" => YOLOTask::Pose,
                 "segment" => YOLOTask::Segment,
                 "obb" => YOLOTask::Obb,
-                x => todo!("Not supported: {x:?} "),
+                x => anyhow::bail!("Not supported task: {x:?}"),
             },
         };
 
@@ -71,18 +113,16 @@ impl YOLO {
                 match &names {
                     None => names = Some((0..nc).map(|x| x.to_string()).collect::<Vec<String>>()),
                     Some(names) => {
-                        assert_eq!(
-                            nc,
-                            names.len(),
-                            "the length of `nc` and `class names` is not equal."
-                        );
+                        if names.len() != nc {
+                            anyhow::bail!("Got {} class names, but `nc` is {nc}", names.len());
+                        }
                     }
                 }
                 nc
             }
             None => match &names {
                 Some(names) => names.len(),
-                None => panic!(
+                None => anyhow::bail!(
                     "Can not parse model without `nc` and `class names`. Try to make it explicit."
                 ),
             },
@@ -98,22 +138,272 @@ impl YOLO {
                 let caps = re.captures(&kpt_string).unwrap();
                 caps.get(1).unwrap().as_str().parse::<usize>().unwrap()
             })
+            .or(options.nk)
             .unwrap_or(0_usize);
-        let nm = if let YOLOTask::Segment = task {
-            engine.oshapes()[1][1] as usize
-        } else {
-            0_usize
+        if let (Some(names_kpt), true) = (&names_kpt, nk > 0) {
+            if names_kpt.len() != nk {
+                anyhow::bail!(
+                    "Got {} keypoint names, but the model has {nk} keypoints",
+                    names_kpt.len()
+                );
+            }
+        }
+        // outputs: explicit indices first, and then [preds, protos..] with protos of rank 4
+        let n_outputs = engine.oshapes().len();
+        let (preds_idx, protos_idxs) = match options.output_indices {
+            Some((preds_idx, protos_idx)) => (
+                preds_idx,
+                options
+                    .protos_indices
+                    .clone()
+                    .unwrap_or(protos_idx.into_iter().collect()),
+            ),
+            None => {
+                let rank4 = (0..n_outputs)
+                    .filter(|&i| engine.oshapes()[i].len() == 4)
+                    .collect::<Vec<_>>();
+                let protos_idxs = match &options.protos_indices {
+                    Some(x) => x.clone(),
+                    None if n_outputs == 2 && rank4.is_empty() => vec![1],
+                    None if !rank4.is_empty() && rank4.len() + 1 == n_outputs => rank4,
+                    None => vec![],
+                };
+                let preds_idx = (0..n_outputs)
+                    .find(|i| !protos_idxs.contains(i))
+                    .unwrap_or(0);
+                (preds_idx, protos_idxs)
+            }
+        };
+        for &i in std::iter::once(&preds_idx).chain(protos_idxs.iter()) {
+            if i >= n_outputs {
+                anyhow::bail!("Output index {i} out of range, model has {n_outputs} outputs");
+            }
+        }
+
+        // raw YOLOv5/v7 heads, one output per level: [b, na, h, w, no] or [b, na * no, h, w]
+        let (preds_idx, protos_idxs, levels_idxs) = match &options.anchors {
+            None => (preds_idx, protos_idxs, vec![]),
+            Some(anchors) => {
+                if !matches!(task, YOLOTask::Detect | YOLOTask::Segment) {
+                    anyhow::bail!(
+                        "Anchor-based decoding supports detect and segment, got {task:?}"
+                    );
+                }
+                if options.end2end == Some(true) {
+                    anyhow::bail!("`anchors` can not be used with end-to-end outputs");
+                }
+                let oshapes = engine.oshapes();
+                let explicit = options
+                    .protos_indices
+                    .clone()
+                    .or(options.output_indices.and_then(|(_, x)| x).map(|x| vec![x]));
+                let rank5 = (0..n_outputs)
+                    .filter(|&i| oshapes[i].len() == 5)
+                    .collect::<Vec<_>>();
+                let levels_idxs = if rank5.is_empty() {
+                    (0..n_outputs)
+                        .filter(|i| !explicit.as_ref().is_some_and(|x| x.contains(i)))
+                        .collect::<Vec<_>>()
+                } else {
+                    rank5
+                };
+                let protos_idxs = match task {
+                    YOLOTask::Segment => explicit.unwrap_or_else(|| {
+                        (0..n_outputs)
+                            .filter(|i| !levels_idxs.contains(i))
+                            .collect()
+                    }),
+                    _ => vec![],
+                };
+                if levels_idxs.len() != anchors.len() {
+                    anyhow::bail!(
+                        "Got {} levels of anchors, but the model has {} raw outputs at {levels_idxs:?}",
+                        anchors.len(),
+                        levels_idxs.len()
+                    );
+                }
+                if let Some(strides) = &options.strides {
+                    if strides.len() != anchors.len() || strides.contains(&0) {
+                        anyhow::bail!(
+                            "Got strides {strides:?} for {} levels of anchors",
+                            anchors.len()
+                        );
+                    }
+                }
+                (levels_idxs[0], protos_idxs, levels_idxs)
+            }
+        };
+
+        // mask coefficients: protos [b, nm, h, w] & preds [b, 4 + nc + nm, anchors], cross-checked
+        let nm = match (&task, protos_idxs.first()) {
+            _ if !levels_idxs.is_empty() => {
+                let anchors = options.anchors.as_deref().unwrap_or_default();
+                let nos = levels_idxs
+                    .iter()
+                    .zip(anchors)
+                    .map(|(&i, x)| Self::level_channels(&engine.oshapes()[i], x.len()))
+                    .collect::<Option<Vec<_>>>();
+                let no = match nos.as_deref() {
+                    Some([no, rest @ ..]) if rest.iter().all(|x| x == no) => *no,
+                    _ => anyhow::bail!(
+                        "Raw outputs {:?} do not match anchors {anchors:?}",
+                        levels_idxs
+                            .iter()
+                            .map(|&i| &engine.oshapes()[i])
+                            .collect::<Vec<_>>()
+                    ),
+                };
+                let nm = no.checked_sub(CXYWH_OFFSET + 1 + nc);
+                match (&task, nm, protos_idxs.is_empty()) {
+                    (YOLOTask::Detect, Some(0), _) => 0,
+                    (YOLOTask::Segment, Some(nm), false) if nm > 0 => nm,
+                    _ => anyhow::bail!(
+                        "{task:?} with nc={nc} does not fit {no} channels per anchor, protos at {protos_idxs:?}"
+                    ),
+                }
+            }
+            (YOLOTask::Segment, Some(&i)) => {
+                let protos_shape = &engine.oshapes()[i];
+                let preds_shape = &engine.oshapes()[preds_idx];
+                if protos_shape.len() != 4 || preds_shape.len() != 3 {
+                    anyhow::bail!(
+                        "Unexpected segment outputs, preds: {preds_shape:?}, protos: {protos_shape:?}"
+                    );
+                }
+                let nm_protos = protos_shape[1];
+                for &j in protos_idxs.iter().skip(1) {
+                    let shape = &engine.oshapes()[j];
+                    if shape.len() != 4 || shape[1] != nm_protos {
+                        anyhow::bail!(
+                            "All protos should be [b, {nm_protos}, h, w], got {protos_shape:?} and {shape:?}"
+                        );
+                    }
+                }
+                let nc_ = nc as isize + options.conf_independent as isize;
+                let channels = preds_shape[if options.anchors_first { 2 } else { 1 }];
+                let nm_preds = (channels > 0).then_some(channels - CXYWH_OFFSET as isize - nc_);
+                match (nm_protos > 0, nm_preds) {
+                    (true, Some(nm_preds)) if nm_preds != nm_protos => anyhow::bail!(
+                        "Mask coefficients mismatch, {nm_protos} from protos {protos_shape:?}, {nm_preds} from preds {preds_shape:?}"
+                    ),
+                    (true, _) => nm_protos as usize,
+                    (false, Some(nm_preds)) if nm_preds > 0 => nm_preds as usize,
+                    _ => anyhow::bail!(
+                        "Can not determine mask coefficients, preds: {preds_shape:?}, protos: {protos_shape:?}"
+                    ),
+                }
+            }
+            (YOLOTask::Segment, None) => anyhow::bail!("Segment model without protos output"),
+            _ => 0_usize,
+        };
+        // end-to-end: [num_dets (b, 1), boxes (b, k, 4), scores (b, k), classes (b, k)]
+        let end2end = levels_idxs.is_empty()
+            && options.end2end.unwrap_or_else(|| {
+                let oshapes = engine.oshapes();
+                let nms4 = oshapes.len() == 4
+                    && oshapes[0].len() == 2
+                    && oshapes[0][1] == 1
+                    && oshapes[1].len() == 3
+                    && oshapes[1][2] == 4;
+                // YOLOv10, not to be confused with anchors-first preds of 2 classes
+                let v10 = oshapes.len() == 1
+                    && !options.anchors_first
+                    && oshapes[0].len() == 3
+                    && oshapes[0][2] == 6;
+                nms4 || v10
+            });
+        if end2end && !matches!(task, YOLOTask::Detect) {
+            anyhow::bail!("End-to-end outputs are only supported for detection, got {task:?}");
+        }
+
+        // preds layout vs task, fail here rather than in `postprocess`
+        if !end2end && levels_idxs.is_empty() {
+            let preds_shape = &engine.oshapes()[preds_idx];
+            let nc_ = nc + options.conf_independent as usize;
+            let channels = match task {
+                YOLOTask::Classify => nc,
+                YOLOTask::Detect => CXYWH_OFFSET + nc_,
+                YOLOTask::Pose => CXYWH_OFFSET + nc_ + KPT_STEP * nk,
+                YOLOTask::Segment => CXYWH_OFFSET + nc_ + nm,
+                YOLOTask::Obb => CXYWH_OFFSET + nc + 1,
+            };
+            let (rank, axis) = match task {
+                YOLOTask::Classify => (2, 1),
+                _ => (3, if options.anchors_first { 2 } else { 1 }),
+            };
+            if preds_shape.len() != rank {
+                anyhow::bail!(
+                    "{task:?} expects a rank-{rank} output, got {preds_shape:?} at index {preds_idx}. Check the task or `with_output_indices`"
+                );
+            }
+            if let YOLOTask::Pose = task {
+                if nk == 0 {
+                    anyhow::bail!("Can not determine keypoints of pose model. Try `with_nk`");
+                }
+            }
+            if preds_shape[axis] > 0 && preds_shape[axis] as usize != channels {
+                anyhow::bail!(
+                    "{task:?} with nc={nc}, nk={nk}, nm={nm} expects {channels} channels at axis {axis}, got {preds_shape:?}. Check the task, `nc`, `anchors_first` or `conf_independent`"
+                );
+            }
+        }
+
+        let image_stats = match (options.image_mean, options.image_std) {
+            (None, None) => None,
+            (mean, std) => {
+                let mean = mean.unwrap_or(vec![0.; 3]);
+                let std = std.unwrap_or(vec![1.; 3]);
+                if mean.len() != 3 || std.len() != 3 {
+                    anyhow::bail!(
+                        "Expect 3 values of `image_mean` & `image_std`, got {mean:?} & {std:?}"
+                    );
+                }
+                if std.iter().any(|&x| x <= 0.) {
+                    anyhow::bail!("`image_std` should be positive, got {std:?}");
+                }
+                Some((mean, std))
+            }
+        };
+        let mut confs = Self::build_confs("confs", &options.confs, 0.4, nc, true)?;
+        if let (Some(path), Some(names)) = (&options.confs_file, &names) {
+            let default = (0..nc).map(|i| confs[i]).collect::<Vec<_>>();
+            confs = DynConf::from_file(path, names, &default)?;
+        }
+        if let Some(id) = options.classes.iter().flatten().find(|&&id| id >= nc) {
+            anyhow::bail!("Class id {id} in `classes` out of range, the model has {nc} classes");
+        }
+        let kconfs = Self::build_confs("kconfs", &options.kconfs, 0.5, nk, true)?;
+        let kpt_temperatures = Self::build_confs(
+            "kpt_temperatures",
+            &options.kpt_temperatures,
+            1.0,
+            nk,
+            false,
+        )?;
+        #[allow(deprecated)]
+        let classify_activation = match options.apply_probs_softmax {
+            true => Activation::Softmax,
+            false => options.classify_activation,
         };
-        let confs = DynConf::new(&options.confs, nc);
-        let kconfs = DynConf::new(&options.kconfs, nk);
         engine.dry_run()?;
 
         Ok(Self {
             engine,
             confs,
             kconfs,
+            kpt_temperatures,
             iou: options.iou,
             apply_nms: options.apply_nms,
+            dedup: options.dedup,
+            final_conf: options.final_conf,
+            full_scores: options.full_scores,
+            nms_exempt_classes: options.nms_exempt_classes,
+            nms_exempt_suppress: options.nms_exempt_suppress,
+            class_agnostic_nms: options.class_agnostic_nms,
+            nms_method: options.nms_method,
+            classes: options.classes,
+            min_box_area: options.min_box_area,
+            max_box_area: options.max_box_area,
             nc,
             nk,
             nm,
@@ -121,296 +411,1120 @@ impl YOLO {
             width,
             batch,
             task,
-            names,
+            names: names.map(Arc::new),
             names_kpt,
-            anchors_first: options.anchors_first,
-            conf_independent: options.conf_independent,
-            apply_probs_softmax: options.apply_probs_softmax,
+            // decoded heads are [b, anchors, xywh_conf_clss_xxx]
+            anchors_first: options.anchors_first || !levels_idxs.is_empty(),
+            conf_independent: options.conf_independent || !levels_idxs.is_empty(),
+            classify_activation,
+            multilabel: options.multilabel,
+            skip_invalid_images: options.skip_invalid_images,
+            preds_idx,
+            protos_idxs,
+            levels_idxs,
+            anchors: options.anchors.unwrap_or_default(),
+            strides: options.strides,
+            mask_smooth: options.mask_smooth,
+            mask_format: options.mask_format,
+            obb_angle: options.obb_angle,
+            decode_masks: options.decode_masks,
+            end2end,
+            input_range: options.input_range,
+            channel_order: options.channel_order,
+            image_stats,
+            resize_mode: options.resize_mode,
+            pad_value: options.pad_value,
+            conf_inclusive: options.conf_inclusive,
+            single_best: options.single_best,
+            processor: None,
+            annotator: None,
+            input: Array::zeros(IxDyn(&[0])),
         })
     }
 
     pub fn run(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
-        let xs_ = match self.task {
+        // zero-sized images
+        let invalid = xs
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| ops::check_image_size(x).is_err())
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        if invalid.is_empty() {
+            return self.forward(xs);
+        }
+        if !self.skip_invalid_images {
+            anyhow::bail!(
+                "Invalid image size at index {invalid:?}: width and height must be non-zero"
+            );
+        }
+
+        // skip them, keep the rest of the batch
+        let xs_valid = xs
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !invalid.contains(i))
+            .map(|(_, x)| x.to_owned())
+            .collect::<Vec<_>>();
+        let mut ys_valid = if xs_valid.is_empty() {
+            vec![]
+        } else {
+            self.forward(&xs_valid)?
+        }
+        .into_iter();
+        Ok((0..xs.len())
+            .map(|i| {
+                if invalid.contains(&i) {
+                    Y::default()
+                } else {
+                    ys_valid.next().unwrap_or_default()
+                }
+            })
+            .collect())
+    }
+
+    /// Run with `conf` & `iou` overridden for this call only
+    pub fn run_with(
+        &mut self,
+        xs: &[DynamicImage],
+        conf: Option<f32>,
+        iou: Option<f32>,
+    ) -> Result<Vec<Y>> {
+        let (confs, iou) = Self::overridden(&self.confs, self.iou, self.nc, conf, iou)?;
+        let saved = (
+            std::mem::replace(&mut self.confs, confs),
+            std::mem::replace(&mut self.iou, iou),
+        );
+        let ys = self.run(xs);
+        (self.confs, self.iou) = saved;
+        ys
+    }
+
+    /// Thresholds for `run_with`, overrides are checked like `set_conf` & `set_iou`
+    fn overridden(
+        confs: &DynConf,
+        iou: f32,
+        nc: usize,
+        conf_: Option<f32>,
+        iou_: Option<f32>,
+    ) -> Result<(DynConf, f32)> {
+        if let Some(c) = conf_.filter(|c| !(0. ..=1.).contains(c)) {
+            anyhow::bail!("Confidence should be in [0, 1], got {c}");
+        }
+        if let Some(x) = iou_.filter(|x| !(0. ..=1.).contains(x)) {
+            anyhow::bail!("IoU should be in [0, 1], got {x}");
+        }
+        let confs = match conf_ {
+            Some(c) => DynConf::new(&[c], nc),
+            None => confs.clone(),
+        };
+        Ok((confs, iou_.unwrap_or(iou)))
+    }
+
+    /// Run on a region of interest, results are in full-frame coordinates
+    pub fn run_roi(&mut self, xs: &[DynamicImage], roi: &Bbox) -> Result<Vec<Y>> {
+        let mut crops = Vec::with_capacity(xs.len());
+        let mut offsets = Vec::with_capacity(xs.len());
+        for x in xs.iter() {
+            let x1 = roi.xmin().max(0.).floor() as u32;
+            let y1 = roi.ymin().max(0.).floor() as u32;
+            let x2 = (roi.xmax().ceil().max(0.) as u32).min(x.width());
+            let y2 = (roi.ymax().ceil().max(0.) as u32).min(x.height());
+            if x2 <= x1 || y2 <= y1 {
+                anyhow::bail!("Empty ROI {roi:?} in image of {}x{}", x.width(), x.height());
+            }
+            crops.push(x.crop_imm(x1, y1, x2 - x1, y2 - y1));
+            offsets.push((x1, y1, x.width(), x.height()));
+        }
+        let ys = self.run(&crops)?;
+        Ok(ys
+            .into_iter()
+            .zip(offsets)
+            .map(|(y, (dx, dy, w, h))| y.with_offset(dx, dy, w, h))
+            .collect())
+    }
+
+    /// Sliced inference for large images: run on overlapping `tile_size` tiles (`overlap` as a
+    /// fraction of `tile_size`), then merge bboxes cut by or duplicated across tile seams
+    pub fn run_tiled(&mut self, x: &DynamicImage, tile_size: u32, overlap: f32) -> Result<Y> {
+        if !matches!(self.task, YOLOTask::Detect) {
+            anyhow::bail!("Tiled inference supports detection, got {:?}", self.task);
+        }
+        if tile_size == 0 || !(0. ..1.).contains(&overlap) {
+            anyhow::bail!("Invalid tiles: size {tile_size}, overlap {overlap} not in [0, 1)");
+        }
+        let (width, height) = (x.width(), x.height());
+        let tiles = Tile::grid(width, height, tile_size, overlap);
+        let mut ys = Vec::with_capacity(tiles.len());
+        for chunk in tiles.chunks(self.batch().max(1) as usize) {
+            let crops = chunk
+                .iter()
+                .map(|t| x.crop_imm(t.x, t.y, t.width, t.height))
+                .collect::<Vec<_>>();
+            for (tile, y) in chunk.iter().zip(self.run(&crops)?) {
+                let y = y.with_offset(tile.x, tile.y, width, height);
+                ys.push((*tile, y.bboxes().cloned().unwrap_or_default()));
+            }
+        }
+        let bboxes = SeamMerge::default().merge(&ys, width, height);
+        Ok(Y::default().with_bboxes(&bboxes))
+    }
+
+    pub fn run_paths<P: AsRef<Path>>(&mut self, paths: &[P]) -> Result<Vec<Y>> {
+        let mut xs = Vec::with_capacity(paths.len());
+        for path in paths.iter() {
+            match DataLoader::try_read(path) {
+                Ok(x) => xs.push(x),
+                Err(err) => {
+                    if !self.skip_invalid_images {
+                        return Err(err);
+                    }
+                    println!("{SAFE_CROSS_MARK} {err}");
+                    xs.push(DynamicImage::new_rgb8(0, 0)); // skipped in `run`
+                }
+            }
+        }
+        self.run(&xs)
+    }
+
+    /// Run over all batches of `dl`, sized by the model batch, annotating with source
+    /// file names if an annotator is set
+    pub fn run_with_dl(&mut self, dl: DataLoader) -> Result<Vec<(PathBuf, Y)>> {
+        let mut yps = Vec::with_capacity(dl.paths().len());
+        for (xs, paths) in dl.with_batch(self.batch().max(1) as usize) {
+            if xs.is_empty() {
+                continue;
+            }
+            let ys = self.run(&xs)?;
+            if let Some(annotator) = &self.annotator {
+                annotator.annotate_with_paths(&xs, &ys, &paths);
+            }
+            yps.extend(paths.into_iter().zip(ys));
+        }
+        Ok(yps)
+    }
+
+    fn forward(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
+        // same-size inputs reuse the buffer of the previous call
+        let mut xs_ = std::mem::take(&mut self.input);
+        let transforms = self.preprocess_into(xs, &mut xs_)?;
+        let ys = self.engine.run(std::slice::from_ref(&xs_));
+        self.input = xs_;
+        let ys = ys?;
+        match &self.processor {
+            Some(processor) => processor.decode(&ys, &self.decode_ctx(xs, &transforms)),
+            None => self.postprocess(ys, xs, &transforms),
+        }
+    }
+
+    /// Model input tensor & per-image transforms
+    pub fn preprocess(&self, xs: &[DynamicImage]) -> Result<(Array<f32, IxDyn>, Vec<Transform>)> {
+        let mut xs_ = Array::zeros(IxDyn(&[0]));
+        let transforms = self.preprocess_into(xs, &mut xs_)?;
+        Ok((xs_, transforms))
+    }
+
+    /// Same as `preprocess`, writing into `xs_` which is only reallocated when its shape changes
+    pub fn preprocess_into(
+        &self,
+        xs: &[DynamicImage],
+        xs_: &mut Array<f32, IxDyn>,
+    ) -> Result<Vec<Transform>> {
+        let (h, w) = (self.height() as u32, self.width() as u32);
+        let transforms = match self.task {
             YOLOTask::Classify => {
-                ops::resize(xs, self.height() as u32, self.width() as u32, "bilinear")?
+                ops::resize_into(xs, h, w, "bilinear", xs_)?;
+                vec![Transform::default(); xs.len()]
             }
-            _ => ops::letterbox(
-                xs,
-                self.height() as u32,
-                self.width() as u32,
-                "catmullRom",
-                Some(114),
-            )?,
+            _ => match self.resize_mode {
+                ResizeMode::Letterbox => {
+                    ops::letterbox_into(xs, h, w, "catmullRom", Some(self.pad_value), xs_)?
+                }
+                ResizeMode::Canvas { scale } => {
+                    ops::canvas_into(xs, h, w, scale, "catmullRom", Some(self.pad_value), xs_)?
+                }
+            },
+        };
+        // element-wise ops on owned arrays keep the allocation
+        let x = self.channel_order.apply_nchw(std::mem::take(xs_));
+        *xs_ = match self.engine.idtypes()[0] {
+            TensorElementType::Uint8 => x, // [0, 255], normalized in-graph
+            _ => self.standardize(self.input_range.apply(x)),
         };
-        let xs_ = ops::normalize(xs_, 0., 255.);
-        let ys = self.engine.run(&[xs_])?;
-        self.postprocess(ys, xs)
+        Ok(transforms)
     }
 
-    pub fn postprocess(&self, xs: Vec<Array<f32, IxDyn>>, xs0: &[DynamicImage]) -> Result<Vec<Y>> {
-        let mut ys = Vec::new();
-        let protos = if xs.len() == 2 { Some(&xs[1]) } else { None };
-        for (idx, preds) in xs[0].axis_iter(Axis(0)).enumerate() {
-            let image_width = xs0[idx].width() as f32;
-            let image_height = xs0[idx].height() as f32;
-
-            // decode
-            match self.task {
-                YOLOTask::Classify => {
-                    let y = if self.apply_probs_softmax {
-                        let exps = preds.mapv(|x| x.exp());
-                        let stds = exps.sum_axis(Axis(0));
-                        exps / stds
-                    } else {
-                        preds.into_owned()
-                    };
+    /// Per-channel `(x - image_mean) / image_std`, if any
+    fn standardize(&self, xs: Array<f32, IxDyn>) -> Array<f32, IxDyn> {
+        match &self.image_stats {
+            Some((mean, std)) => ops::standardize(xs, mean, std),
+            None => xs,
+        }
+    }
 
-                    ys.push(
-                        Y::default().with_probs(
-                            Prob::default()
-                                .with_probs(&y.into_raw_vec())
-                                .with_names(self.names.to_owned()),
-                        ),
-                    );
+    /// Run on a ready model input [b, 3, h, w], e.g. another model's output. `normalized` tells
+    /// values in [0, 1] from pixels in [0, 255], the latter are divided by 255 as usual.
+    /// Results are in input coordinates, `DecodeCtx::xs0` holds blank images of the input size.
+    pub fn run_tensor(&mut self, xs: Array<f32, IxDyn>, normalized: bool) -> Result<Vec<Y>> {
+        let (n, h, w) = match xs.shape() {
+            &[n, 3, h, w] => (n, h as u32, w as u32),
+            shape => anyhow::bail!("Expect input tensor of [b, 3, h, w], got {shape:?}"),
+        };
+        let xs = self.channel_order.apply_nchw(xs);
+        let xs_ = match (self.engine.idtypes()[0], normalized) {
+            (TensorElementType::Uint8, true) => xs * 255.,
+            (TensorElementType::Uint8, false) => xs,
+            (_, true) => self.standardize(self.input_range.apply_unit(xs)),
+            (_, false) => self.standardize(self.input_range.apply(xs)),
+        };
+        let xs0 = vec![DynamicImage::new_luma8(w, h); n];
+        let transforms = vec![Transform::default(); n];
+        let ys = self.inference(xs_)?;
+        match &self.processor {
+            Some(processor) => processor.decode(&ys, &self.decode_ctx(&xs0, &transforms)),
+            None => self.postprocess(ys, &xs0, &transforms),
+        }
+    }
+
+    /// Raw model outputs
+    pub fn inference(&mut self, xs: Array<f32, IxDyn>) -> Result<Vec<Array<f32, IxDyn>>> {
+        self.engine.run(&[xs])
+    }
+
+    /// Replace the built-in decoding with a custom one
+    pub fn with_processor(mut self, x: Box<dyn PostProcessor>) -> Self {
+        self.processor = Some(x);
+        self
+    }
+
+    pub fn with_annotator(mut self, x: Annotator) -> Self {
+        self.annotator = Some(x);
+        self
+    }
+
+    pub fn set_annotator(&mut self, x: Annotator) {
+        self.annotator = Some(x);
+    }
+
+    pub fn annotator(&self) -> Option<&Annotator> {
+        self.annotator.as_ref()
+    }
+
+    /// Annotate with the attached `Annotator`, if any
+    pub fn annotate(&self, xs: &[DynamicImage], ys: &[Y]) {
+        self.annotate_with(xs, ys, None)
+    }
+
+    /// Annotate with `annotator` for this call only, or the instance one if None
+    pub fn annotate_with(&self, xs: &[DynamicImage], ys: &[Y], annotator: Option<&Annotator>) {
+        if let Some(annotator) = annotator.or(self.annotator.as_ref()) {
+            annotator.annotate(xs, ys);
+        }
+    }
+
+    /// `run`, then annotate with `annotator` or the instance one
+    pub fn run_annotated(
+        &mut self,
+        xs: &[DynamicImage],
+        annotator: Option<&Annotator>,
+    ) -> Result<Vec<Y>> {
+        let ys = self.run(xs)?;
+        self.annotate_with(xs, &ys, annotator);
+        Ok(ys)
+    }
+
+    /// `run`, plus annotated images in memory by `annotator` or the instance one, nothing saved
+    pub fn run_plotted(
+        &mut self,
+        xs: &[DynamicImage],
+        annotator: Option<&Annotator>,
+    ) -> Result<(Vec<Y>, Vec<DynamicImage>)> {
+        let ys = self.run(xs)?;
+        let annotator = match annotator.or(self.annotator.as_ref()) {
+            Some(x) => x,
+            None => anyhow::bail!("No annotator, pass one or attach it with `with_annotator`"),
+        };
+        let plots = xs
+            .iter()
+            .zip(ys.iter())
+            .map(|(x, y)| annotator.plot_to_image(x, y))
+            .collect();
+        Ok((ys, plots))
+    }
+
+    /// Empty (`default`), one value for all, or one per class with the last one repeated
+    fn build_confs(
+        what: &str,
+        x: &[f32],
+        default: f32,
+        n: usize,
+        unit_range: bool,
+    ) -> Result<DynConf> {
+        if n > 0 && x.len() > n {
+            anyhow::bail!("Got {} `{what}`, but only {n} are expected", x.len());
+        }
+        if let Some(c) = x
+            .iter()
+            .find(|c| (unit_range && !(0. ..=1.).contains(*c)) || !c.is_finite())
+        {
+            anyhow::bail!("Invalid value in `{what}`: {c}");
+        }
+        if x.is_empty() {
+            Ok(DynConf::new(&[default], n))
+        } else {
+            Ok(DynConf::new(x, n))
+        }
+    }
+
+    pub fn set_conf(&mut self, x: &[f32]) -> Result<()> {
+        if let Some(c) = x.iter().find(|c| !(0. ..=1.).contains(*c)) {
+            anyhow::bail!("Confidence should be in [0, 1], got {c}");
+        }
+        self.confs = DynConf::new(x, self.nc);
+        Ok(())
+    }
+
+    pub fn set_iou(&mut self, x: f32) -> Result<()> {
+        if !(0. ..=1.).contains(&x) {
+            anyhow::bail!("IoU should be in [0, 1], got {x}");
+        }
+        self.iou = x;
+        Ok(())
+    }
+
+    pub fn set_names(&mut self, x: &[&str]) -> Result<()> {
+        if x.len() != self.nc {
+            anyhow::bail!(
+                "Got {} class names, but the model has {} classes",
+                x.len(),
+                self.nc
+            );
+        }
+        self.names = Some(Arc::new(x.iter().map(|x| x.to_string()).collect()));
+        Ok(())
+    }
+
+    pub fn decode_ctx<'a>(
+        &'a self,
+        xs0: &'a [DynamicImage],
+        transforms: &'a [Transform],
+    ) -> DecodeCtx<'a> {
+        DecodeCtx {
+            xs0,
+            transforms,
+            height: self.height() as u32,
+            width: self.width() as u32,
+            task: &self.task,
+            nc: self.nc,
+            nk: self.nk,
+            nm: self.nm,
+            confs: &self.confs,
+            kconfs: &self.kconfs,
+            iou: self.iou,
+            names: self.names.as_deref().map(|x| x.as_slice()),
+            names_kpt: self.names_kpt.as_deref(),
+            anchors_first: self.anchors_first,
+        }
+    }
+
+    pub fn postprocess(
+        &self,
+        xs: Vec<Array<f32, IxDyn>>,
+        xs0: &[DynamicImage],
+        transforms: &[Transform],
+    ) -> Result<Vec<Y>> {
+        if self.end2end {
+            return self.postprocess_end2end(&xs, xs0, transforms);
+        }
+        // protos from fine to coarse
+        let mut protos = match (&self.task, self.decode_masks) {
+            (YOLOTask::Segment, true) => self.protos_idxs.iter().map(|&i| &xs[i]).collect(),
+            _ => Vec::new(),
+        };
+        protos.sort_by_key(|x| std::cmp::Reverse(x.shape()[2] * x.shape()[3]));
+        for x in protos.iter() {
+            Self::check_batch(x, xs0.len())?;
+        }
+        let decoded = match self.levels_idxs.is_empty() {
+            true => None,
+            false => Some(self.decode_levels(&xs)?),
+        };
+        let preds_all = decoded.as_ref().unwrap_or(&xs[self.preds_idx]);
+        Self::check_batch(preds_all, xs0.len())?;
+        let preds = preds_all
+            .axis_iter(Axis(0))
+            .take(xs0.len()) // static batch: extra rows are padding
+            .enumerate()
+            .collect::<Vec<_>>();
+        let decode = |(idx, preds): &(usize, ArrayView<f32, IxDyn>)| {
+            self.postprocess_one(preds, &xs0[*idx], &transforms[*idx], &protos, *idx)
+        };
+        // images are independent, order is kept
+        #[cfg(feature = "rayon")]
+        let ys = {
+            use rayon::prelude::*;
+            preds.par_iter().map(decode).collect::<Result<Vec<_>>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let ys = preds.iter().map(decode).collect::<Result<Vec<_>>>()?;
+        Ok(ys)
+    }
+
+    /// Decode predictions of one image, `idx` in the batch
+    fn postprocess_one(
+        &self,
+        preds: &ArrayView<f32, IxDyn>,
+        x0: &DynamicImage,
+        transform: &Transform,
+        protos: &[&Array<f32, IxDyn>],
+        idx: usize,
+    ) -> Result<Y> {
+        let image_width = x0.width() as f32;
+        let image_height = x0.height() as f32;
+        let y = match self.task {
+            YOLOTask::Classify => {
+                let y = if self.multilabel {
+                    preds.mapv(|x| 1. / (1. + (-x).exp()))
+                } else {
+                    match self.classify_activation {
+                        Activation::Softmax => {
+                            let exps = preds.mapv(|x| x.exp());
+                            let stds = exps.sum_axis(Axis(0));
+                            exps / stds
+                        }
+                        Activation::None => preds.to_owned(),
+                    }
+                };
+
+                let mut prob = Prob::default()
+                    .with_probs(&y.into_raw_vec())
+                    .with_shared_names(self.names.clone());
+
+                // multi-label: keep every class above its threshold
+                if self.multilabel {
+                    let labels = prob
+                        .probs()
+                        .iter()
+                        .enumerate()
+                        .filter(|(id, &x)| self.pass(x, self.confs[*id]))
+                        .map(|(id, _)| id)
+                        .collect::<Vec<_>>();
+                    prob = prob.with_labels(&labels);
                 }
-                YOLOTask::Obb => {
-                    let mut y_mbrs: Vec<Mbr> = Vec::new();
-                    let ratio = (self.width() as f32 / image_width)
-                        .min(self.height() as f32 / image_height);
-                    for pred in preds.axis_iter(if self.anchors_first { Axis(0) } else { Axis(1) })
+                Y::default().with_probs(prob)
+            }
+            YOLOTask::Obb => {
+                let mut y_mbrs: Vec<Mbr> = Vec::new();
+                for pred in preds.axis_iter(if self.anchors_first { Axis(0) } else { Axis(1) }) {
+                    // xywhclsr
+                    let xywh = pred.slice(s![0..CXYWH_OFFSET]);
+                    let clss = pred.slice(s![CXYWH_OFFSET..CXYWH_OFFSET + self.nc]);
+                    let radians = self.obb_angle.to_canonical(pred[pred.len() - 1]);
+                    let (id, &confidence) = clss
+                        .into_iter()
+                        .enumerate()
+                        .max_by(|a, b| a.1.total_cmp(b.1))
+                        .unwrap();
+                    if !self.is_wanted(id) || !self.pass(confidence, self.confs[id]) {
+                        continue;
+                    }
+                    // single best: only a new best is re-scaled
+                    if self.single_best
+                        && y_mbrs.first().is_some_and(|x| x.confidence() >= confidence)
                     {
-                        // xywhclsr
-                        let xywh = pred.slice(s![0..CXYWH_OFFSET]);
-                        let clss = pred.slice(s![CXYWH_OFFSET..CXYWH_OFFSET + self.nc]);
-                        let radians = pred[pred.len() - 1];
-                        let (id, &confidence) = clss
-                            .into_iter()
-                            .enumerate()
-                            .max_by(|a, b| a.1.total_cmp(b.1))
-                            .unwrap();
-                        if confidence < self.confs[id] {
-                            continue;
-                        }
+                        continue;
+                    }
 
-                        // re-scale
-                        let cx = xywh[0] / ratio;
-                        let cy = xywh[1] / ratio;
-                        let w = xywh[2] / ratio;
-                        let h = xywh[3] / ratio;
-                        let (w, h, radians) = if w > h {
-                            (w, h, radians)
-                        } else {
-                            (h, w, radians + std::f32::consts::PI / 2.)
-                        };
-                        let radians = radians % std::f32::consts::PI;
-                        y_mbrs.push(
-                            Mbr::from_cxcywhr(
-                                cx as f64,
-                                cy as f64,
-                                w as f64,
-                                h as f64,
-                                radians as f64,
-                            )
+                    // re-scale
+                    let (cx, cy) = transform.descale_xy(xywh[0], xywh[1]);
+                    let w = transform.descale_len(xywh[2]);
+                    let h = transform.descale_len(xywh[3]);
+                    if !self.is_area_wanted(w * h) {
+                        continue;
+                    }
+                    if self.single_best {
+                        y_mbrs.clear();
+                    }
+                    let (w, h, radians) = if w > h {
+                        (w, h, radians)
+                    } else {
+                        (h, w, radians + std::f32::consts::PI / 2.)
+                    };
+                    let radians = radians.rem_euclid(std::f32::consts::PI);
+                    y_mbrs.push(
+                        Mbr::from_cxcywhr(cx as f64, cy as f64, w as f64, h as f64, radians as f64)
                             .with_confidence(confidence)
                             .with_id(id as isize)
                             .with_name(self.names.as_ref().map(|names| names[id].to_owned())),
-                        );
-                    }
-                    ys.push(Y::default().with_mbrs(&y_mbrs).apply_mbrs_nms(self.iou));
+                    );
+                }
+                let mut y = Y::default().with_mbrs(&y_mbrs).apply_mbrs_nms_exempt(
+                    self.iou,
+                    &self.nms_exempt_classes,
+                    self.nms_exempt_suppress,
+                    self.class_agnostic_nms,
+                );
+                if let Some(conf) = self.final_conf {
+                    y = y.apply_min_conf(conf);
                 }
-                _ => {
-                    let mut y_bboxes: Vec<Bbox> = Vec::new();
-                    let ratio = (self.width() as f32 / image_width)
-                        .min(self.height() as f32 / image_height);
+                y
+            }
+            _ => {
+                let mut y_bboxes: Vec<Bbox> = Vec::new();
 
-                    // bboxes
-                    for (i, pred) in preds
-                        .axis_iter(if self.anchors_first { Axis(0) } else { Axis(1) })
+                // bboxes
+                for (i, pred) in preds
+                    .axis_iter(if self.anchors_first { Axis(0) } else { Axis(1) })
+                    .enumerate()
+                {
+                    let bbox = pred.slice(s![0..CXYWH_OFFSET]);
+                    let (conf_, clss) = if self.conf_independent {
+                        (
+                            pred[CXYWH_OFFSET],
+                            pred.slice(s![CXYWH_OFFSET + 1..CXYWH_OFFSET + self.nc + 1]),
+                        )
+                    } else {
+                        (1.0, pred.slice(s![CXYWH_OFFSET..CXYWH_OFFSET + self.nc]))
+                    };
+                    let (id, &confidence) = clss
+                        .into_iter()
                         .enumerate()
+                        .max_by(|a, b| a.1.total_cmp(b.1))
+                        .unwrap();
+                    let confidence = confidence * conf_;
+                    if !self.is_wanted(id) || !self.pass(confidence, self.confs[id]) {
+                        continue;
+                    }
+                    // single best: only a new best is re-scaled
+                    if self.single_best
+                        && y_bboxes
+                            .first()
+                            .is_some_and(|x| x.confidence() >= confidence)
                     {
-                        let bbox = pred.slice(s![0..CXYWH_OFFSET]);
-                        let (conf_, clss) = if self.conf_independent {
-                            (
-                                pred[CXYWH_OFFSET],
-                                pred.slice(s![CXYWH_OFFSET + 1..CXYWH_OFFSET + self.nc + 1]),
-                            )
-                        } else {
-                            (1.0, pred.slice(s![CXYWH_OFFSET..CXYWH_OFFSET + self.nc]))
-                        };
-                        let (id, &confidence) = clss
-                            .into_iter()
-                            .enumerate()
-                            .max_by(|a, b| a.1.total_cmp(b.1))
-                            .unwrap();
-                        let confidence = confidence * conf_;
-                        if confidence < self.confs[id] {
-                            continue;
-                        }
-
-                        // re-scale
-                        let cx = bbox[0] / ratio;
-                        let cy = bbox[1] / ratio;
-                        let w = bbox[2] / ratio;
-                        let h = bbox[3] / ratio;
-                        let x = cx - w / 2.;
-                        let y = cy - h / 2.;
-                        let x = x.max(0.0).min(image_width);
-                        let y = y.max(0.0).min(image_height);
-                        let y_bbox = Bbox::default()
-                            .with_xywh(x, y, w, h)
-                            .with_confidence(confidence)
-                            .with_id(id as isize)
-                            .with_id_born(i as isize)
-                            .with_name(self.names.as_ref().map(|names| names[id].to_owned()));
-                        y_bboxes.push(y_bbox);
+                        continue;
                     }
 
-                    // nms
-                    let mut y = Y::default().with_bboxes(&y_bboxes);
-                    if self.apply_nms {
-                        y = y.apply_bboxes_nms(self.iou);
+                    // re-scale
+                    let (cx, cy) = transform.descale_xy(bbox[0], bbox[1]);
+                    let w = transform.descale_len(bbox[2]);
+                    let h = transform.descale_len(bbox[3]);
+                    let x = cx - w / 2.;
+                    let y = cy - h / 2.;
+                    let x = x.max(0.0).min(image_width);
+                    let y = y.max(0.0).min(image_height);
+                    if !self.is_area_wanted(w * h) {
+                        continue;
                     }
+                    if self.single_best {
+                        y_bboxes.clear();
+                    }
+                    let y_bbox = Bbox::default()
+                        .with_xywh(x, y, w, h)
+                        .with_confidence(confidence)
+                        .with_id(id as isize)
+                        .with_id_born(i as isize)
+                        .with_name(self.names.as_ref().map(|names| names[id].to_owned()));
+                    y_bboxes.push(y_bbox);
+                }
 
-                    // keypoints
-                    if let YOLOTask::Pose = self.task {
-                        if let Some(bboxes) = y.bboxes() {
-                            let mut y_kpts: Vec<Vec<Keypoint>> = Vec::new();
-                            for bbox in bboxes.iter() {
-                                let pred = if self.anchors_first {
-                                    preds.slice(s![
-                                        bbox.id_born(),
-                                        preds.shape()[1] - KPT_STEP * self.nk..,
-                                    ])
-                                } else {
-                                    preds.slice(s![
-                                        preds.shape()[0] - KPT_STEP * self.nk..,
-                                        bbox.id_born(),
-                                    ])
+                // nms
+                let mut y = Y::default().with_bboxes(&y_bboxes);
+                if self.apply_nms && !self.single_best {
+                    y = match self.nms_method {
+                        NmsMethod::Hard => y.apply_bboxes_nms_exempt(
+                            self.iou,
+                            &self.nms_exempt_classes,
+                            self.nms_exempt_suppress,
+                            self.class_agnostic_nms,
+                        ),
+                        method => {
+                            // decayed confidences are thresholded again
+                            let y = y.apply_bboxes_soft_nms(
+                                self.iou,
+                                method,
+                                &self.nms_exempt_classes,
+                                self.nms_exempt_suppress,
+                                self.class_agnostic_nms,
+                            );
+                            let bboxes = y
+                                .bboxes()
+                                .map(|x| x.as_slice())
+                                .unwrap_or_default()
+                                .iter()
+                                .filter(|x| self.pass(x.confidence(), self.confs[x.id() as usize]))
+                                .cloned()
+                                .collect::<Vec<_>>();
+                            y.with_bboxes(&bboxes)
+                        }
+                    };
+                }
+                if let Some(conf) = self.final_conf {
+                    y = y.apply_min_conf(conf);
+                }
+
+                // all classes' scores, scaled by objectness like the argmax one
+                if self.full_scores {
+                    if let Some(bboxes) = y.bboxes() {
+                        let scores = bboxes
+                            .iter()
+                            .map(|bbox| {
+                                let anchor = bbox.id_born() as usize;
+                                let clss = Self::anchor_slice(
+                                    preds,
+                                    anchor,
+                                    CXYWH_OFFSET + self.conf_independent as usize,
+                                    self.nc,
+                                    self.anchors_first,
+                                );
+                                let conf_ = match self.conf_independent {
+                                    true => Self::anchor_slice(
+                                        preds,
+                                        anchor,
+                                        CXYWH_OFFSET,
+                                        1,
+                                        self.anchors_first,
+                                    )[0],
+                                    false => 1.,
                                 };
+                                clss.into_iter().map(|x| x * conf_).collect()
+                            })
+                            .collect::<Vec<Vec<f32>>>();
+                        y = y.with_scores(&scores);
+                    }
+                }
 
-                                let mut kpts_: Vec<Keypoint> = Vec::new();
-                                for i in 0..self.nk {
-                                    let kx = pred[KPT_STEP * i] / ratio;
-                                    let ky = pred[KPT_STEP * i + 1] / ratio;
-                                    let kconf = pred[KPT_STEP * i + 2];
-                                    if kconf < self.kconfs[i] {
-                                        kpts_.push(Keypoint::default());
-                                    } else {
-                                        kpts_.push(
-                                            Keypoint::default()
-                                                .with_id(i as isize)
-                                                .with_confidence(kconf)
-                                                .with_name(
-                                                    self.names_kpt
-                                                        .as_ref()
-                                                        .map(|names| names[i].to_owned()),
-                                                )
-                                                .with_xy(
-                                                    kx.max(0.0f32).min(image_width),
-                                                    ky.max(0.0f32).min(image_height),
-                                                ),
-                                        );
-                                    }
+                // keypoints
+                if let YOLOTask::Pose = self.task {
+                    if let Some(bboxes) = y.bboxes() {
+                        let mut y_kpts: Vec<Vec<Keypoint>> = Vec::new();
+                        for bbox in bboxes.iter() {
+                            let pred = Self::anchor_tail(
+                                preds,
+                                bbox.id_born() as usize,
+                                KPT_STEP * self.nk,
+                                self.anchors_first,
+                            );
+
+                            let mut kpts_: Vec<Keypoint> = Vec::new();
+                            for i in 0..self.nk {
+                                let (kx, ky) = transform
+                                    .descale_xy(pred[KPT_STEP * i], pred[KPT_STEP * i + 1]);
+                                let kconf = ops::temperature_scale(
+                                    pred[KPT_STEP * i + 2],
+                                    self.kpt_temperatures[i],
+                                );
+                                // always nk keypoints, invisible ones are placeholders
+                                if !self.pass(kconf, self.kconfs[i]) {
+                                    kpts_.push(Keypoint::default().with_id(i as isize).with_name(
+                                        self.names_kpt.as_ref().map(|names| names[i].to_owned()),
+                                    ));
+                                } else {
+                                    kpts_.push(
+                                        Keypoint::default()
+                                            .with_id(i as isize)
+                                            .with_confidence(kconf)
+                                            .with_name(
+                                                self.names_kpt
+                                                    .as_ref()
+                                                    .map(|names| names[i].to_owned()),
+                                            )
+                                            .with_xy(
+                                                kx.max(0.0f32).min(image_width),
+                                                ky.max(0.0f32).min(image_height),
+                                            ),
+                                    );
                                 }
-                                y_kpts.push(kpts_);
                             }
-                            y = y.with_keypoints(&y_kpts);
+                            y_kpts.push(kpts_);
                         }
+                        y = y.with_keypoints(&y_kpts);
                     }
+                }
 
-                    // masks
-                    if let YOLOTask::Segment = self.task {
-                        if let Some(bboxes) = y.bboxes() {
-                            let mut y_polygons: Vec<Polygon> = Vec::new();
-                            for bbox in bboxes.iter() {
-                                let coefs = if self.anchors_first {
-                                    preds
-                                        .slice(s![bbox.id_born(), preds.shape()[1] - self.nm..])
-                                        .to_vec()
-                                } else {
-                                    preds
-                                        .slice(s![preds.shape()[0] - self.nm.., bbox.id_born()])
-                                        .to_vec()
+                // masks
+                if let (YOLOTask::Segment, true) = (&self.task, self.decode_masks) {
+                    if let Some(bboxes) = y.bboxes() {
+                        let mut y_polygons: Vec<Polygon> = Vec::new();
+                        let mut y_masks: Vec<Mask> = Vec::new();
+                        let mut y_rles: Vec<Rle> = Vec::new();
+                        for bbox in bboxes.iter() {
+                            let coefs = Self::anchor_tail(
+                                preds,
+                                bbox.id_born() as usize,
+                                self.nm,
+                                self.anchors_first,
+                            );
+                            let proto = Self::select_protos(
+                                protos,
+                                bbox.width().min(bbox.height()) * transform.ratio,
+                                self.width() as f32,
+                            )
+                            .slice(s![idx, .., .., ..]);
+
+                            // coefs * proto -> mask
+                            let (nm, nh, nw) = proto.dim();
+                            let coefs = Array::from_shape_vec((1, nm), coefs)?; // (n, nm)
+                            let proto = proto.to_owned().into_shape((nm, nh * nw))?; // (nm, nh*nw)
+                            let mask = coefs.dot(&proto).into_shape((nh, nw, 1))?; // (nh, nw, n)
+
+                            // build image from ndarray
+                            let mask: ImageBuffer<image::Luma<_>, Vec<f32>> =
+                                match ImageBuffer::from_raw(
+                                    nw as u32,
+                                    nh as u32,
+                                    mask.clone().into_raw_vec(),
+                                ) {
+                                    Some(buf) => buf,
+                                    None => continue,
                                 };
-                                let proto = protos.unwrap().slice(s![idx, .., .., ..]);
-
-                                // coefs * proto -> mask
-                                let (nm, nh, nw) = proto.dim();
-                                let coefs = Array::from_shape_vec((1, nm), coefs)?; // (n, nm)
-                                let proto = proto.to_owned().into_shape((nm, nh * nw))?; // (nm, nh*nw)
-                                let mask = coefs.dot(&proto).into_shape((nh, nw, 1))?; // (nh, nw, n)
-
-                                // build image from ndarray
-                                let mask: ImageBuffer<image::Luma<_>, Vec<f32>> =
-                                    match ImageBuffer::from_raw(
-                                        nw as u32,
-                                        nh as u32,
-                                        mask.clone().into_raw_vec(),
-                                    ) {
-                                        Some(buf) => buf,
-                                        None => continue,
-                                    };
-                                let mask = image::DynamicImage::from(mask);
+                            let mask = image::DynamicImage::from(mask);
 
-                                // rescale
-                                let mask_original = ops::descale_mask(
+                            // rescale
+                            let mask_original = match self.resize_mode {
+                                ResizeMode::Letterbox => ops::descale_mask(
                                     mask,
                                     nw as f32,
                                     nh as f32,
                                     image_width,
                                     image_height,
-                                );
-                                let mut mask_original = mask_original.into_luma8();
-
-                                // crop mask
-                                for y in 0..image_height as usize {
-                                    for x in 0..image_width as usize {
-                                        if x < bbox.xmin() as usize
-                                            || x > bbox.xmax() as usize
-                                            || y < bbox.ymin() as usize
-                                            || y > bbox.ymax() as usize
-                                        // || mask_original.get_pixel(x as u32, y as u32).0 < [127]
-                                        {
-                                            mask_original.put_pixel(
-                                                x as u32,
-                                                y as u32,
-                                                image::Luma([0u8]),
-                                            );
-                                        }
+                                ),
+                                ResizeMode::Canvas { .. } => ops::descale_mask_with(
+                                    mask,
+                                    transform,
+                                    self.width() as f32,
+                                    self.height() as f32,
+                                    image_width,
+                                    image_height,
+                                ),
+                            };
+                            let mut mask_original = mask_original.into_luma8();
+
+                            // smooth jagged edges
+                            if let Some(x) = &self.mask_smooth {
+                                mask_original =
+                                    ops::smooth_mask(&mask_original, x.open, x.close, x.sigma);
+                            }
+
+                            // crop mask
+                            for y in 0..image_height as usize {
+                                for x in 0..image_width as usize {
+                                    if x < bbox.xmin() as usize
+                                        || x > bbox.xmax() as usize
+                                        || y < bbox.ymin() as usize
+                                        || y > bbox.ymax() as usize
+                                    // || mask_original.get_pixel(x as u32, y as u32).0 < [127]
+                                    {
+                                        mask_original.put_pixel(
+                                            x as u32,
+                                            y as u32,
+                                            image::Luma([0u8]),
+                                        );
                                     }
                                 }
+                            }
 
-                                // get masks from image
-                                let contours: Vec<imageproc::contours::Contour<i32>> =
-                                    imageproc::contours::find_contours_with_threshold(
-                                        &mask_original,
-                                        0,
-                                    );
-                                let polygon = match contours
-                                    .iter()
-                                    .map(|x| {
-                                        Polygon::default()
-                                            .with_id(bbox.id())
-                                            .with_points_imageproc(&x.points)
-                                            .with_name(bbox.name().cloned())
-                                    })
-                                    .max_by(|x, y| x.area().total_cmp(&y.area()))
-                                {
-                                    None => continue,
-                                    Some(x) => x,
-                                };
-                                y_polygons.push(polygon);
+                            // materialize
+                            match self.mask_format {
+                                MaskFormat::Bitmap => y_masks.push(
+                                    Mask::default()
+                                        .with_mask(DynamicImage::from(mask_original))
+                                        .with_id(bbox.id())
+                                        .with_name(bbox.name().cloned())
+                                        .with_confidence(bbox.confidence()),
+                                ),
+                                MaskFormat::Rle => y_rles.push(
+                                    Rle::from_luma(&mask_original)
+                                        .with_id(bbox.id())
+                                        .with_name(bbox.name().cloned())
+                                        .with_confidence(bbox.confidence()),
+                                ),
+                                MaskFormat::Polygon => {
+                                    // get masks from image
+                                    let contours: Vec<imageproc::contours::Contour<i32>> =
+                                        imageproc::contours::find_contours_with_threshold(
+                                            &mask_original,
+                                            ops::MASK_THRESHOLD,
+                                        );
+                                    let polygon = match contours
+                                        .iter()
+                                        .map(|x| {
+                                            Polygon::default()
+                                                .with_id(bbox.id())
+                                                .with_points_imageproc(&x.points)
+                                                .with_name(bbox.name().cloned())
+                                        })
+                                        .max_by(|x, y| x.area().total_cmp(&y.area()))
+                                    {
+                                        None => continue,
+                                        Some(x) => x,
+                                    };
+                                    y_polygons.push(polygon);
+                                }
                             }
-                            y = y.with_polygons(&y_polygons);
                         }
+                        y = match self.mask_format {
+                            MaskFormat::Bitmap => y.with_masks(&y_masks),
+                            MaskFormat::Rle => y.with_rles(&y_rles),
+                            MaskFormat::Polygon => y.with_polygons(&y_polygons),
+                        };
                     }
-                    ys.push(y);
                 }
+                y
             }
+        };
+        Ok(y)
+    }
+
+    /// Whether a box area, in original image pixels, is within `[min_box_area, max_box_area]`
+    fn is_area_wanted(&self, area: f32) -> bool {
+        !(self.min_box_area.is_some_and(|x| area < x)
+            || self.max_box_area.is_some_and(|x| area > x))
+    }
+
+    /// Whether a class id is in `classes`, if any
+    fn is_wanted(&self, id: usize) -> bool {
+        match &self.classes {
+            Some(x) => x.contains(&id),
+            None => true,
+        }
+    }
+
+    /// Whether a score passes its threshold
+    fn pass(&self, x: f32, threshold: f32) -> bool {
+        if self.conf_inclusive {
+            x >= threshold
+        } else {
+            x > threshold
+        }
+    }
+
+    /// Last `n` channels of an anchor: keypoints, mask coefficients
+    fn anchor_tail(
+        preds: &ArrayView<f32, IxDyn>,
+        anchor: usize,
+        n: usize,
+        anchors_first: bool,
+    ) -> Vec<f32> {
+        let channels = preds.shape()[if anchors_first { 1 } else { 0 }];
+        Self::anchor_slice(preds, anchor, channels - n, n, anchors_first)
+    }
+
+    /// `n` channels from `start` of one anchor
+    fn anchor_slice(
+        preds: &ArrayView<f32, IxDyn>,
+        anchor: usize,
+        start: usize,
+        n: usize,
+        anchors_first: bool,
+    ) -> Vec<f32> {
+        if anchors_first {
+            // [anchors, channels]
+            preds.slice(s![anchor, start..start + n]).to_vec()
+        } else {
+            // [channels, anchors]
+            preds.slice(s![start..start + n, anchor]).to_vec()
+        }
+    }
+
+    /// The coarsest protos with enough cells across the box, fine to coarse
+    fn select_protos<'a>(
+        protos: &[&'a Array<f32, IxDyn>],
+        box_side: f32,
+        input_width: f32,
+    ) -> &'a Array<f32, IxDyn> {
+        const MIN_CELLS: f32 = 16.;
+        protos
+            .iter()
+            .rev()
+            .find(|x| box_side / (input_width / x.shape()[3] as f32) >= MIN_CELLS)
+            .unwrap_or(&protos[0])
+    }
+
+    /// Channels per anchor of a raw head, [b, na, h, w, no] or [b, na * no, h, w]
+    fn level_channels(shape: &[isize], na: usize) -> Option<usize> {
+        match *shape {
+            [_, a, _, _, no] if a as usize == na && no > 0 => Some(no as usize),
+            [_, c, _, _] if c > 0 && (c as usize).checked_rem(na) == Some(0) => {
+                Some(c as usize / na)
+            }
+            _ => None,
+        }
+    }
+
+    /// Decode raw YOLOv5/v7 heads into [b, anchors, xywh_conf_clss_xxx]
+    fn decode_levels(&self, xs: &[Array<f32, IxDyn>]) -> Result<Array<f32, IxDyn>> {
+        let no = CXYWH_OFFSET + 1 + self.nc + self.nm;
+        let mut levels = Vec::new();
+        for (k, (&i, anchors)) in self.levels_idxs.iter().zip(self.anchors.iter()).enumerate() {
+            let stride = self.strides.as_ref().map(|x| x[k] as f32);
+            levels.push(Self::decode_level(
+                &xs[i],
+                anchors,
+                stride,
+                self.height() as f32,
+                no,
+                self.nc,
+            )?);
+        }
+        let levels = levels.iter().map(|x| x.view()).collect::<Vec<_>>();
+        Ok(ndarray::concatenate(Axis(1), &levels)?)
+    }
+
+    /// One level: sigmoid on xywh, conf and classes (not mask coefficients),
+    /// then `xy = (2s - 0.5 + grid) * stride` and `wh = (2s)^2 * anchor`
+    fn decode_level(
+        x: &Array<f32, IxDyn>,
+        anchors: &[(f32, f32)],
+        stride: Option<f32>,
+        height: f32,
+        no: usize,
+        nc: usize,
+    ) -> Result<Array<f32, IxDyn>> {
+        let na = anchors.len();
+        let x = match *x.shape() {
+            [_, a, _, _, n] if a == na && n == no => x.view(),
+            [b, c, h, w] if c == na * no => x
+                .view()
+                .into_shape(IxDyn(&[b, na, no, h, w]))?
+                .permuted_axes(IxDyn(&[0, 1, 3, 4, 2])),
+            _ => anyhow::bail!(
+                "Raw head of shape {:?} does not fit {na} anchors of {no} channels",
+                x.shape()
+            ),
+        };
+        let (b, h, w) = (x.shape()[0], x.shape()[2], x.shape()[3]);
+        let stride = stride.unwrap_or(height / h.max(1) as f32);
+        let mut ys = Array::zeros(IxDyn(&[b, na * h * w, no]));
+        for bi in 0..b {
+            for (a, &(aw, ah)) in anchors.iter().enumerate() {
+                for gy in 0..h {
+                    for gx in 0..w {
+                        let mut y = ys.slice_mut(s![bi, (a * h + gy) * w + gx, ..]);
+                        y.assign(&x.slice(s![bi, a, gy, gx, ..]));
+                        y.slice_mut(s![..CXYWH_OFFSET + 1 + nc])
+                            .mapv_inplace(|x| 1. / (1. + (-x).exp()));
+                        y[0] = (y[0] * 2. - 0.5 + gx as f32) * stride;
+                        y[1] = (y[1] * 2. - 0.5 + gy as f32) * stride;
+                        y[2] = (y[2] * 2.).powi(2) * aw;
+                        y[3] = (y[3] * 2.).powi(2) * ah;
+                    }
+                }
+            }
+        }
+        Ok(ys)
+    }
+
+    /// Output batch dim (axis 0) should cover all inputs
+    fn check_batch(x: &Array<f32, IxDyn>, n: usize) -> Result<()> {
+        match x.shape().first() {
+            Some(&b) if b >= n => Ok(()),
+            _ => anyhow::bail!(
+                "Output batch dim can not be reconciled with {n} inputs, output shape: {:?}",
+                x.shape()
+            ),
+        }
+    }
+
+    /// Decode in-graph NMS outputs, boxes are xyxy. Either [num_dets, boxes, scores, classes],
+    /// or a single [b, k, 6] of (x1, y1, x2, y2, conf, cls) like YOLOv10
+    fn postprocess_end2end(
+        &self,
+        xs: &[Array<f32, IxDyn>],
+        xs0: &[DynamicImage],
+        transforms: &[Transform],
+    ) -> Result<Vec<Y>> {
+        for x in xs.iter() {
+            Self::check_batch(x, xs0.len())?;
+        }
+        match xs {
+            [x] if x.ndim() == 3 && x.shape()[2] == 6 => {}
+            [_, _, _, _] => {}
+            _ => anyhow::bail!(
+                "End-to-end model should have 4 outputs or one [b, k, 6], got {:?}",
+                xs.iter().map(|x| x.shape()).collect::<Vec<_>>()
+            ),
+        }
+        // (index, xyxy, confidence, class) of each image
+        let dets = |idx: usize| -> Vec<(usize, [f32; 4], f32, usize)> {
+            match xs {
+                [x] => (0..x.shape()[1])
+                    .map(|i| {
+                        let v = |j: usize| x[&[idx, i, j][..]];
+                        (i, [v(0), v(1), v(2), v(3)], v(4), v(5) as usize)
+                    })
+                    .collect(),
+                [num_dets, boxes, scores, classes] => {
+                    let n = (num_dets[&[idx, 0][..]] as usize).min(boxes.shape()[1]);
+                    (0..n)
+                        .map(|i| {
+                            let v = |j: usize| boxes[&[idx, i, j][..]];
+                            (
+                                i,
+                                [v(0), v(1), v(2), v(3)],
+                                scores[&[idx, i][..]],
+                                classes[&[idx, i][..]] as usize,
+                            )
+                        })
+                        .collect()
+                }
+                _ => unreachable!(),
+            }
+        };
+        let mut ys = Vec::new();
+        for (idx, x0) in xs0.iter().enumerate() {
+            let (image_width, image_height) = (x0.width() as f32, x0.height() as f32);
+            let transform = &transforms[idx];
+            let mut y_bboxes: Vec<Bbox> = Vec::new();
+            for (i, xyxy, confidence, id) in dets(idx) {
+                if id >= self.nc || !self.is_wanted(id) || !self.pass(confidence, self.confs[id]) {
+                    continue;
+                }
+                if self.single_best
+                    && y_bboxes
+                        .first()
+                        .is_some_and(|x| x.confidence() >= confidence)
+                {
+                    continue;
+                }
+                let (x1, y1) = transform.descale_xy(xyxy[0], xyxy[1]);
+                let (x2, y2) = transform.descale_xy(xyxy[2], xyxy[3]);
+                if !self.is_area_wanted((x2 - x1) * (y2 - y1)) {
+                    continue;
+                }
+                if self.single_best {
+                    y_bboxes.clear();
+                }
+                y_bboxes.push(
+                    Bbox::default()
+                        .with_xyxy(
+                            x1.max(0.).min(image_width),
+                            y1.max(0.).min(image_height),
+                            x2.max(0.).min(image_width),
+                            y2.max(0.).min(image_height),
+                        )
+                        .with_confidence(confidence)
+                        .with_id(id as isize)
+                        .with_id_born(i as isize)
+                        .with_name(self.names.as_ref().map(|names| names[id].to_owned())),
+                );
+            }
+            let mut y = Y::default().with_bboxes(&y_bboxes);
+            if let Some((iou, conf_delta)) = self.dedup {
+                y = y.apply_bboxes_dedup(iou, conf_delta);
+            }
+            if let Some(conf) = self.final_conf {
+                y = y.apply_min_conf(conf);
+            }
+            ys.push(y);
         }
         Ok(ys)
     }
 
+    /// Inputs, outputs and metadata of the underlying model
+    pub fn engine(&self) -> &OrtEngine {
+        &self.engine
+    }
+
     pub fn batch(&self) -> isize {
         self.batch.opt
     }
@@ -436,3 +1550,145 @@ impl YOLO {
         })
     }
 }
+
+#[cfg(test)]
+mod tests_yolo {
+    use super::YOLO;
+    use crate::DynConf;
+    use ndarray::{s, Array, IxDyn};
+
+    // 3 anchors, channels: xywh + 2 classes + 2 tail values, tail = anchor * 10 + j
+    fn preds(anchors_first: bool) -> Array<f32, IxDyn> {
+        let (na, no) = (3, 8);
+        let x = Array::from_shape_fn(
+            (na, no),
+            |(a, c)| {
+                if c >= 6 {
+                    (a * 10 + c - 6) as f32
+                } else {
+                    -1.
+                }
+            },
+        );
+        if anchors_first {
+            x.into_dyn()
+        } else {
+            x.reversed_axes()
+                .as_standard_layout()
+                .into_owned()
+                .into_dyn()
+        }
+    }
+
+    #[test]
+    fn build_confs() {
+        let confs = YOLO::build_confs("confs", &[0.4, 0.15], 0.4, 3, true).unwrap();
+        assert_eq!((confs[0], confs[1], confs[2]), (0.4, 0.15, 0.15));
+        let confs = YOLO::build_confs("confs", &[], 0.4, 2, true).unwrap();
+        assert_eq!((confs[0], confs[1]), (0.4, 0.4));
+        assert!(YOLO::build_confs("confs", &[0.1, 0.2, 0.3], 0.4, 2, true).is_err());
+        assert!(YOLO::build_confs("confs", &[1.5], 0.4, 2, true).is_err());
+        assert!(YOLO::build_confs("kpt_temperatures", &[1.5], 1., 2, false).is_ok());
+        assert!(YOLO::build_confs("kconfs", &[0.5], 0.5, 0, true).is_ok());
+    }
+
+    #[test]
+    fn run_with_overrides() {
+        let confs = DynConf::new(&[0.25], 2);
+        let (c, iou) = YOLO::overridden(&confs, 0.45, 2, Some(0.6), None).unwrap();
+        assert_eq!((c[0], c[1], iou), (0.6, 0.6, 0.45));
+        let (c, iou) = YOLO::overridden(&confs, 0.45, 2, None, Some(0.7)).unwrap();
+        assert_eq!((c, iou), (confs.clone(), 0.7));
+        assert!(YOLO::overridden(&confs, 0.45, 2, Some(1.5), Some(0.7)).is_err());
+        assert!(YOLO::overridden(&confs, 0.45, 2, Some(0.6), Some(-0.1)).is_err());
+        assert!(YOLO::overridden(&confs, 0.45, 2, Some(f32::NAN), None).is_err());
+        assert_eq!(confs, DynConf::new(&[0.25], 2));
+    }
+
+    #[test]
+    fn decode_raw_heads() {
+        assert_eq!(YOLO::level_channels(&[1, 3, 20, 20, 85], 3), Some(85));
+        assert_eq!(YOLO::level_channels(&[1, 255, -1, -1], 3), Some(85));
+        assert_eq!(YOLO::level_channels(&[1, 256, 20, 20], 3), None);
+        assert_eq!(YOLO::level_channels(&[1, 2, 20, 20, 85], 3), None);
+
+        // [b, na, h, w, no], zero logits: sigmoid 0.5 everywhere
+        let anchors = [(10., 13.), (16., 30.)];
+        let x = Array::zeros(IxDyn(&[1, 2, 2, 3, 7]));
+        let y = YOLO::decode_level(&x, &anchors, None, 16., 7, 2).unwrap();
+        assert_eq!(y.shape(), &[1, 12, 7]);
+        let row = |a: usize, gy: usize, gx: usize| {
+            y.slice(s![0, (a * 2 + gy) * 3 + gx, ..])
+                .iter()
+                .copied()
+                .collect::<Vec<f32>>()
+        };
+        // stride 16 / 2 = 8, xy = (0.5 + grid) * 8, wh = anchor
+        assert_eq!(row(0, 0, 0), vec![4., 4., 10., 13., 0.5, 0.5, 0.5]);
+        assert_eq!(row(1, 1, 2), vec![20., 12., 16., 30., 0.5, 0.5, 0.5]);
+
+        // [b, na * no, h, w] holds the same values, mask coefficients stay raw
+        let x = Array::from_shape_fn(IxDyn(&[1, 2, 8, 2, 3]), |d| match d[2] {
+            7 => 0.25,
+            _ => 0.,
+        })
+        .into_shape(IxDyn(&[1, 16, 2, 3]))
+        .unwrap();
+        let y = YOLO::decode_level(&x, &anchors, Some(4.), 16., 8, 2).unwrap();
+        assert_eq!(
+            y.slice(s![0, 11, ..]).iter().copied().collect::<Vec<f32>>(),
+            vec![10., 6., 16., 30., 0.5, 0.5, 0.5, 0.25]
+        );
+        assert!(YOLO::decode_level(&x, &anchors, None, 16., 7, 2).is_err());
+    }
+
+    #[test]
+    fn anchor_tail_both_layouts() {
+        for anchors_first in [true, false] {
+            let preds = preds(anchors_first);
+            for anchor in 0..3 {
+                let tail = YOLO::anchor_tail(&preds.view(), anchor, 2, anchors_first);
+                let expected = vec![(anchor * 10) as f32, (anchor * 10 + 1) as f32];
+                assert_eq!(tail, expected, "anchors_first: {anchors_first}");
+            }
+        }
+    }
+
+    #[test]
+    fn anchor_slice_class_scores() {
+        for anchors_first in [true, false] {
+            let x = Array::from_shape_fn((3, 7), |(a, c)| (a * 100 + c) as f32);
+            let preds = if anchors_first {
+                x.into_dyn()
+            } else {
+                x.reversed_axes().into_dyn()
+            };
+            let clss = YOLO::anchor_slice(&preds.view(), 2, 4, 3, anchors_first);
+            assert_eq!(
+                clss,
+                vec![204., 205., 206.],
+                "anchors_first: {anchors_first}"
+            );
+        }
+    }
+
+    #[test]
+    fn anchor_tail_pose_and_segment_widths() {
+        // pose: 17 kpts * 3, segment: 32 coefs
+        for n in [51, 32] {
+            for anchors_first in [true, false] {
+                let channels = 4 + 1 + n;
+                let x = Array::from_shape_fn((2, channels), |(a, c)| (a * 1000 + c) as f32);
+                let preds = if anchors_first {
+                    x.into_dyn()
+                } else {
+                    x.reversed_axes().into_dyn()
+                };
+                let tail = YOLO::anchor_tail(&preds.view(), 1, n, anchors_first);
+                assert_eq!(tail.len(), n);
+                assert_eq!(tail[0], (1000 + 5) as f32);
+                assert_eq!(tail[n - 1], (1000 + channels - 1) as f32);
+            }
+        }
+    }
+}
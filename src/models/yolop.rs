@@ -37,7 +37,7 @@ impl YOLOPv2 {
     }
 
     pub fn run(&mut self, xs: &[DynamicImage]) -> Result<Vec<Y>> {
-        let xs_ = ops::letterbox(
+        let (xs_, _) = ops::letterbox(
             xs,
             self.height() as u32,
             self.width() as u32,
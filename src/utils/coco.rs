@@ -119,3 +119,19 @@ pub const NAMES_80: [&str; 80] = [
     "hair drier",
     "toothbrush",
 ];
+
+/// Official COCO category ids of `NAMES_80`, in the same order
+pub const CATEGORY_IDS_80: [usize; 80] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 27, 28,
+    31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55,
+    56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 67, 70, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 84,
+    85, 86, 87, 88, 89, 90,
+];
+
+/// Official COCO category id of a class name in `NAMES_80`
+pub fn category_id(name: &str) -> Option<usize> {
+    NAMES_80
+        .iter()
+        .position(|x| *x == name)
+        .map(|i| CATEGORY_IDS_80[i])
+}
@@ -1,5 +1,8 @@
+use crate::Keypoint;
+
 /// Bounding Box 2D
 #[derive(Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bbox {
     x: f32,
     y: f32,
@@ -207,7 +210,36 @@ impl Bbox {
     }
 
     pub fn iou(&self, other: &Bbox) -> f32 {
-        self.intersect(other) / self.union(other)
+        let union = self.union(other);
+        if union > 0. {
+            self.intersect(other) / union
+        } else {
+            0.
+        }
+    }
+
+    pub fn center(&self) -> Keypoint {
+        (self.cx(), self.cy()).into()
+    }
+
+    /// Overlapping region of two boxes, `None` if they do not overlap
+    pub fn intersection(&self, other: &Bbox) -> Option<Bbox> {
+        let (x1, y1) = (self.xmin().max(other.xmin()), self.ymin().max(other.ymin()));
+        let (x2, y2) = (self.xmax().min(other.xmax()), self.ymax().min(other.ymax()));
+        if x2 <= x1 || y2 <= y1 {
+            return None;
+        }
+        Some(Bbox::default().with_xyxy(x1, y1, x2, y2))
+    }
+
+    /// Smallest box enclosing both, see `union` for the area of the union
+    pub fn enclosing(&self, other: &Bbox) -> Bbox {
+        Bbox::default().with_xyxy(
+            self.xmin().min(other.xmin()),
+            self.ymin().min(other.ymin()),
+            self.xmax().max(other.xmax()),
+            self.ymax().max(other.ymax()),
+        )
     }
 
     pub fn contains(&self, other: &Bbox) -> bool {
@@ -221,6 +253,7 @@ impl Bbox {
 #[cfg(test)]
 mod tests_bbox {
     use super::Bbox;
+    use crate::Keypoint;
 
     #[test]
     fn new() {
@@ -262,5 +295,19 @@ mod tests_bbox {
         assert!(!bbox1.contains(&bbox2));
         assert!(bbox1.contains(&bbox3));
         assert!(bbox2.contains(&bbox3));
+
+        assert_eq!(bbox1.iou(&bbox2), 16. / 34.);
+        assert_eq!(Bbox::default().iou(&Bbox::default()), 0.);
+        assert_eq!(bbox2.center(), Keypoint::from((3.5, 3.5)));
+        assert_eq!(
+            bbox1.intersection(&bbox2),
+            Some(Bbox::default().with_xyxy(1., 1., 5., 5.))
+        );
+        let far = Bbox::default().with_xyxy(10., 10., 12., 12.);
+        assert_eq!(bbox1.intersection(&far), None);
+        assert_eq!(
+            bbox1.enclosing(&far),
+            Bbox::default().with_xyxy(0., 0., 12., 12.)
+        );
     }
 }
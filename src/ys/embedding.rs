@@ -3,6 +3,7 @@ use ndarray::{Array, Axis, Ix2, IxDyn};
 
 /// Embedding
 #[derive(Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Embedding(Array<f32, IxDyn>);
 
 impl std::fmt::Debug for Embedding {
@@ -33,6 +34,69 @@ impl Embedding {
         self
     }
 
+    /// L2-normalize the flattened embedding in place, zero vectors are left as is
+    pub fn normalize(&mut self) {
+        let n = self.0.iter().map(|x| x * x).sum::<f32>().sqrt();
+        if n > f32::EPSILON {
+            self.0.mapv_inplace(|x| x / n);
+        }
+    }
+
+    /// Dot product of two flattened embeddings
+    pub fn dot(&self, other: &Embedding) -> Result<f32> {
+        if self.0.len() != other.0.len() {
+            anyhow::bail!(
+                "Embedding lengths mismatch: {} vs {}",
+                self.0.len(),
+                other.0.len()
+            );
+        }
+        Ok(self.0.iter().zip(other.0.iter()).map(|(a, b)| a * b).sum())
+    }
+
+    /// Same as `cosine`, 0 if lengths mismatch or either is a zero vector
+    pub fn cosine_similarity(&self, other: &Embedding) -> f32 {
+        self.cosine(other).unwrap_or(0.)
+    }
+
+    /// Cosine similarity of two flattened embeddings
+    pub fn cosine(&self, other: &Embedding) -> Result<f32> {
+        if self.0.len() != other.0.len() {
+            anyhow::bail!(
+                "Embedding lengths mismatch: {} vs {}",
+                self.0.len(),
+                other.0.len()
+            );
+        }
+        let dot = self.dot(other)?;
+        let n1 = self.0.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let n2 = other.0.iter().map(|x| x * x).sum::<f32>().sqrt();
+        Ok(dot / (n1 * n2).max(f32::EPSILON))
+    }
+
+    /// Top `k` (index, probability) of the flattened logits after softmax, ties by lower index
+    pub fn topk(&self, k: usize) -> Vec<(usize, f32)> {
+        let max = self.0.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exps = self.0.iter().map(|x| (x - max).exp()).collect::<Vec<_>>();
+        let sum = exps.iter().sum::<f32>().max(f32::EPSILON);
+        let mut xs = exps
+            .into_iter()
+            .map(|x| x / sum)
+            .enumerate()
+            .collect::<Vec<_>>();
+        xs.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        xs.truncate(k);
+        xs
+    }
+
+    /// Split a batched (n, ndim) embedding into n embeddings
+    pub fn rows(&self) -> Vec<Embedding> {
+        self.0
+            .axis_iter(Axis(0))
+            .map(|x| Embedding::new(x.to_owned().into_dyn()))
+            .collect()
+    }
+
     pub fn dot2(&self, other: &Embedding) -> Result<Vec<Vec<f32>>> {
         // (m, ndim) * (n, ndim).t => (m, n)
         let query = self.0.to_owned().into_dimensionality::<Ix2>()?;
@@ -45,3 +109,37 @@ impl Embedding {
         Ok(matrix)
     }
 }
+
+#[cfg(test)]
+mod tests_embedding {
+    use super::Embedding;
+    use ndarray::{Array, IxDyn};
+
+    #[test]
+    fn similarity() {
+        let e = |x: Vec<f32>| Embedding::new(Array::from_shape_vec(IxDyn(&[x.len()]), x).unwrap());
+        let mut a = e(vec![3., 4.]);
+        a.normalize();
+        assert_eq!(a.embedding().as_slice().unwrap(), &[0.6, 0.8]);
+        let mut zero = e(vec![0., 0.]);
+        zero.normalize();
+        assert_eq!(zero.embedding().as_slice().unwrap(), &[0., 0.]);
+
+        assert!((a.dot(&e(vec![1., 1.])).unwrap() - 1.4).abs() < 1e-6);
+        assert!(a.dot(&e(vec![1.])).is_err());
+        assert!((a.cosine_similarity(&e(vec![6., 8.])) - 1.).abs() < 1e-6);
+        assert!(a.cosine_similarity(&e(vec![-4., 3.])).abs() < 1e-6);
+        assert_eq!(a.cosine_similarity(&zero), 0.);
+        assert_eq!(a.cosine_similarity(&e(vec![1.])), 0.);
+    }
+
+    #[test]
+    fn topk() {
+        let x = Array::from_shape_vec(IxDyn(&[1, 4]), vec![1., 3., 3., 0.]).unwrap();
+        let topk = Embedding::new(x).topk(3);
+        assert_eq!(topk.iter().map(|x| x.0).collect::<Vec<_>>(), vec![1, 2, 0]);
+        assert!((topk[0].1 - topk[1].1).abs() < 1e-6);
+        let total = 2. * 3f32.exp() + 1f32.exp() + 1.;
+        assert!((topk[2].1 - 1f32.exp() / total).abs() < 1e-6);
+    }
+}
@@ -2,6 +2,7 @@ use std::ops::{Add, Div, Mul, Sub};
 
 /// Keypoint 2D
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Keypoint {
     x: f32,
     y: f32,
@@ -236,6 +237,11 @@ impl Keypoint {
         label
     }
 
+    /// Invisible keypoints are placeholders at (0, 0) with zero confidence, keeping their slot id
+    pub fn visible(&self) -> bool {
+        self.confidence > 0.
+    }
+
     pub fn is_origin(&self) -> bool {
         self.x == 0.0_f32 && self.y == 0.0_f32
     }
@@ -244,6 +250,30 @@ impl Keypoint {
         ((self.x - other.x).powf(2.0) + (self.y - other.y).powf(2.0)).sqrt()
     }
 
+    /// Distance between two visible keypoints, `None` if either is invisible
+    pub fn distance(&self, other: &Keypoint) -> Option<f32> {
+        if self.visible() && other.visible() {
+            Some(self.distance_from(other))
+        } else {
+            None
+        }
+    }
+
+    /// Angle at vertex `b` formed by `a-b-c` in degrees, within [0, 180].
+    /// `None` if any keypoint is invisible or a limb has zero length.
+    pub fn joint_angle(a: &Keypoint, b: &Keypoint, c: &Keypoint) -> Option<f32> {
+        if !(a.visible() && b.visible() && c.visible()) {
+            return None;
+        }
+        let (ba, bc) = (a.clone() - b.clone(), c.clone() - b.clone());
+        let norm = ba.distance_from_origin() * bc.distance_from_origin();
+        if norm <= f32::EPSILON {
+            return None;
+        }
+        let cos = (ba.x * bc.x + ba.y * bc.y) / norm;
+        Some(cos.clamp(-1., 1.).acos().to_degrees())
+    }
+
     pub fn distance_from_origin(&self) -> f32 {
         (self.x.powf(2.0) + self.y.powf(2.0)).sqrt()
     }
@@ -342,5 +372,25 @@ mod tests_keypoint {
         let kpt1 = Keypoint::from((0., 0.));
         let kpt2 = Keypoint::from((5., 0.));
         assert_eq!(kpt1.distance_from(&kpt2), 5.);
+        assert!(!Keypoint::default().with_id(3).visible());
+        assert!(Keypoint::default().with_confidence(0.1).visible());
+    }
+
+    #[test]
+    fn pose() {
+        let kpt = |x: f32, y: f32| Keypoint::from((x, y)).with_confidence(0.9);
+        let (shoulder, elbow, wrist) = (kpt(0., 0.), kpt(3., 0.), kpt(3., 4.));
+        assert_eq!(shoulder.distance(&wrist), Some(5.));
+        assert_eq!(shoulder.distance(&Keypoint::default()), None);
+
+        let angle = Keypoint::joint_angle(&shoulder, &elbow, &wrist).unwrap();
+        assert!((angle - 90.).abs() < 1e-4);
+        let angle = Keypoint::joint_angle(&shoulder, &elbow, &kpt(6., 0.)).unwrap();
+        assert!((angle - 180.).abs() < 1e-4);
+        assert_eq!(
+            Keypoint::joint_angle(&shoulder, &elbow, &Keypoint::default()),
+            None
+        );
+        assert_eq!(Keypoint::joint_angle(&elbow, &elbow, &wrist), None);
     }
 }
@@ -1,6 +1,11 @@
 use image::DynamicImage;
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "MaskRepr", into = "MaskRepr")
+)]
 pub struct Mask {
     mask: DynamicImage,
     mask_vec: Vec<u8>,
@@ -32,6 +37,62 @@ impl std::fmt::Debug for Mask {
     }
 }
 
+/// Serialized form of `Mask`: the luma image as raw bytes with its size
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MaskRepr {
+    width: u32,
+    height: u32,
+    mask: Vec<u8>,
+    mask_vec: Vec<u8>,
+    id: isize,
+    name: Option<String>,
+    confidence: f32,
+}
+
+#[cfg(feature = "serde")]
+impl From<Mask> for MaskRepr {
+    fn from(x: Mask) -> Self {
+        let mask = x.mask.into_luma8();
+        Self {
+            width: mask.width(),
+            height: mask.height(),
+            mask: mask.into_raw(),
+            mask_vec: x.mask_vec,
+            id: x.id,
+            name: x.name,
+            confidence: x.confidence,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MaskRepr> for Mask {
+    type Error = anyhow::Error;
+
+    fn try_from(x: MaskRepr) -> anyhow::Result<Self> {
+        if x.mask.len() as u64 != x.width as u64 * x.height as u64 {
+            anyhow::bail!(
+                "Mask of {}x{} needs {} bytes, got {}",
+                x.width,
+                x.height,
+                x.width as u64 * x.height as u64,
+                x.mask.len()
+            );
+        }
+        let mask = image::GrayImage::from_raw(x.width, x.height, x.mask)
+            .map(DynamicImage::from)
+            .ok_or_else(|| anyhow::anyhow!("Invalid mask of {}x{}", x.width, x.height))?;
+        Ok(Self {
+            mask,
+            mask_vec: x.mask_vec,
+            id: x.id,
+            name: x.name,
+            confidence: x.confidence,
+        })
+    }
+}
+
 impl Mask {
     pub fn with_mask(mut self, x: DynamicImage) -> Self {
         self.mask = x;
@@ -53,6 +114,11 @@ impl Mask {
         self
     }
 
+    pub fn with_confidence(mut self, x: f32) -> Self {
+        self.confidence = x;
+        self
+    }
+
     pub fn mask(&self) -> &DynamicImage {
         &self.mask
     }
@@ -73,4 +139,54 @@ impl Mask {
     pub fn confidence(&self) -> f32 {
         self.confidence
     }
+
+    /// Number of foreground (non-zero) pixels
+    pub fn area(&self) -> usize {
+        self.mask.to_luma8().pixels().filter(|p| p.0[0] > 0).count()
+    }
+
+    /// Mean (x, y) of foreground pixels, None if empty
+    pub fn centroid(&self) -> Option<(f32, f32)> {
+        let (mut n, mut sx, mut sy) = (0usize, 0f64, 0f64);
+        for (x, y, p) in self.mask.to_luma8().enumerate_pixels() {
+            if p.0[0] > 0 {
+                n += 1;
+                sx += x as f64;
+                sy += y as f64;
+            }
+        }
+        (n > 0).then(|| ((sx / n as f64) as f32, (sy / n as f64) as f32))
+    }
+}
+
+#[cfg(test)]
+mod tests_mask {
+    use super::Mask;
+    use image::{DynamicImage, GrayImage, Luma};
+
+    #[test]
+    fn area_centroid() {
+        let mut x = GrayImage::new(10, 10);
+        for (i, j) in [(2, 3), (4, 3), (2, 5), (4, 5)] {
+            x.put_pixel(i, j, Luma([255]));
+        }
+        let mask = Mask::default().with_mask(DynamicImage::from(x));
+        assert_eq!(mask.area(), 4);
+        assert_eq!(mask.centroid(), Some((3., 4.)));
+
+        let empty = Mask::default().with_mask(DynamicImage::from(GrayImage::new(4, 4)));
+        assert_eq!(empty.area(), 0);
+        assert_eq!(empty.centroid(), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_size_mismatch() {
+        let mask = Mask::default().with_mask(DynamicImage::from(GrayImage::new(3, 2)));
+        let mut v = serde_json::to_value(&mask).unwrap();
+        assert_eq!(serde_json::from_value::<Mask>(v.clone()).unwrap(), mask);
+        v["width"] = 4.into();
+        let err = serde_json::from_value::<Mask>(v).unwrap_err();
+        assert!(err.to_string().contains("needs 8 bytes, got 6"), "{err}");
+    }
 }
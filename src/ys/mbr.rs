@@ -2,6 +2,7 @@ use geo::{coord, line_string, Area, BooleanOps, Coord, EuclideanDistance, LineSt
 
 /// Minimum Bounding Rectangle
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Mbr {
     ls: LineString,
     id: isize,
@@ -197,7 +198,12 @@ impl Mbr {
     }
 
     pub fn iou(&self, other: &Mbr) -> f32 {
-        self.intersect(other) / self.union(other)
+        let union = self.union(other);
+        if union > 0. {
+            self.intersect(other) / union
+        } else {
+            0.
+        }
     }
 }
 
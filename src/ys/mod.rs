@@ -5,7 +5,10 @@ mod mask;
 mod mbr;
 mod polygon;
 mod prob;
+mod rle;
 mod y;
+mod y_builder;
+mod y_collector;
 
 pub use bbox::Bbox;
 pub use embedding::Embedding;
@@ -14,4 +17,7 @@ pub use mask::Mask;
 pub use mbr::Mbr;
 pub use polygon::Polygon;
 pub use prob::Prob;
+pub use rle::Rle;
 pub use y::Y;
+pub use y_builder::YBuilder;
+pub use y_collector::YCollector;
@@ -6,6 +6,7 @@ use geo::{
 use crate::{Bbox, Mbr};
 
 #[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polygon {
     polygon: geo::Polygon,
     id: isize,
@@ -46,6 +47,39 @@ impl Polygon {
         self
     }
 
+    /// Polygons with holes from traced contours, outer rings counter-clockwise & holes clockwise
+    pub fn from_contours(contours: &[imageproc::contours::Contour<i32>]) -> Vec<geo::Polygon> {
+        use geo::orient::{Direction, Orient};
+        use imageproc::contours::BorderType;
+
+        let ring = |x: &imageproc::contours::Contour<i32>| {
+            LineString::from(
+                x.points
+                    .iter()
+                    .map(|p| coord! { x: p.x as f64, y: p.y as f64 })
+                    .collect::<Vec<_>>(),
+            )
+        };
+        contours
+            .iter()
+            .enumerate()
+            .filter(|(_, x)| x.border_type == BorderType::Outer)
+            .map(|(i, x)| {
+                let holes = contours
+                    .iter()
+                    .filter(|h| h.border_type == BorderType::Hole && h.parent == Some(i))
+                    .map(ring)
+                    .collect();
+                geo::Polygon::new(ring(x), holes).orient(Direction::Default)
+            })
+            .collect()
+    }
+
+    pub fn with_confidence(mut self, x: f32) -> Self {
+        self.confidence = x;
+        self
+    }
+
     pub fn with_polygon(mut self, x: geo::Polygon) -> Self {
         self.polygon = x;
         self
@@ -1,18 +1,31 @@
+use std::sync::Arc;
+
 /// Probabilities for classification
 #[derive(Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Prob {
     probs: Vec<f32>,
-    names: Option<Vec<String>>,
+    names: Option<Arc<Vec<String>>>, // shared across a batch
+    labels: Option<Vec<usize>>,      // selected ids in multi-label classification
 }
 
 impl std::fmt::Debug for Prob {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_struct("").field("Top5", &self.topk(5)).finish()
+        match self.labels() {
+            Some(labels) => f.debug_struct("").field("Labels", &labels).finish(),
+            None => f.debug_struct("").field("Top5", &self.topk(5)).finish(),
+        }
     }
 }
 
 impl Prob {
     pub fn with_names(mut self, x: Option<Vec<String>>) -> Self {
+        self.names = x.map(Arc::new);
+        self
+    }
+
+    /// Names shared with other `Prob`s without copying
+    pub fn with_shared_names(mut self, x: Option<Arc<Vec<String>>>) -> Self {
         self.names = x;
         self
     }
@@ -22,12 +35,17 @@ impl Prob {
         self
     }
 
+    pub fn with_labels(mut self, x: &[usize]) -> Self {
+        self.labels = Some(x.to_vec());
+        self
+    }
+
     pub fn probs(&self) -> &Vec<f32> {
         &self.probs
     }
 
     pub fn names(&self) -> Option<&Vec<String>> {
-        self.names.as_ref()
+        self.names.as_deref()
     }
 
     pub fn topk(&self, k: usize) -> Vec<(usize, f32, Option<String>)> {
@@ -37,19 +55,68 @@ impl Prob {
             .enumerate()
             .map(|(a, b)| (a, *b))
             .collect::<Vec<_>>();
-        probs.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+        probs.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0))); // ties: lower id first
         let mut topk = Vec::new();
         for &(id, confidence) in probs.iter().take(k) {
-            topk.push((
-                id,
-                confidence,
-                self.names.as_ref().map(|names| names[id].to_owned()),
-            ));
+            topk.push((id, confidence, self.name(id)));
         }
         topk
     }
 
+    fn name(&self, id: usize) -> Option<String> {
+        self.names.as_ref().and_then(|names| names.get(id).cloned())
+    }
+
+    /// Selected classes of multi-label classification, sorted by score. Ids without a prob are skipped.
+    pub fn labels(&self) -> Option<Vec<(usize, f32, Option<String>)>> {
+        self.labels.as_ref().map(|labels| {
+            let mut xs = labels
+                .iter()
+                .filter_map(|&id| Some((id, *self.probs.get(id)?, self.name(id))))
+                .collect::<Vec<_>>();
+            xs.sort_by(|a, b| b.1.total_cmp(&a.1));
+            xs
+        })
+    }
+
     pub fn top1(&self) -> (usize, f32, Option<String>) {
         self.topk(1)[0].to_owned()
     }
 }
+
+#[cfg(test)]
+mod tests_prob {
+    use super::Prob;
+    use std::sync::Arc;
+
+    #[test]
+    fn shared_names() {
+        let names = Some(Arc::new(vec![
+            "cat".to_string(),
+            "dog".to_string(),
+            "fox".to_string(),
+        ]));
+        let a = Prob::default()
+            .with_probs(&[0.1, 0.7, 0.2])
+            .with_shared_names(names.clone());
+        let b = Prob::default()
+            .with_probs(&[0.6, 0.1, 0.3])
+            .with_shared_names(names.clone());
+        assert!(std::ptr::eq(a.names().unwrap(), b.names().unwrap()));
+        assert_eq!(a.top1(), (1, 0.7, Some("dog".to_string())));
+        assert_eq!(b.topk(2)[1], (2, 0.3, Some("fox".to_string())));
+    }
+
+    #[test]
+    fn labels_out_of_range() {
+        let x = Prob::default()
+            .with_probs(&[0.9, 0.2, 0.8])
+            .with_names(Some(vec!["cat".to_string(), "dog".to_string()]))
+            .with_labels(&[0, 2, 5]);
+        assert_eq!(
+            x.labels().unwrap(),
+            vec![(0, 0.9, Some("cat".to_string())), (2, 0.8, None)]
+        );
+        assert!(format!("{x:?}").contains("Labels"));
+    }
+}
@@ -0,0 +1,245 @@
+use image::GrayImage;
+
+/// COCO Run-Length Encoding of a binary mask
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rle {
+    size: [u32; 2],   // [height, width]
+    counts: Vec<u32>, // column-major, starts with the count of 0s
+    id: isize,
+    name: Option<String>,
+    confidence: f32,
+}
+
+impl Default for Rle {
+    fn default() -> Self {
+        Self {
+            size: [0, 0],
+            counts: vec![],
+            id: -1,
+            name: None,
+            confidence: 0.,
+        }
+    }
+}
+
+impl std::fmt::Debug for Rle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Rle")
+            .field("size", &self.size)
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("confidence", &self.confidence)
+            .finish()
+    }
+}
+
+impl Rle {
+    /// Encode from a luma mask, non-zero pixels are foreground
+    pub fn from_luma(mask: &GrayImage) -> Self {
+        let (w, h) = mask.dimensions();
+        let mut counts: Vec<u32> = Vec::new();
+        let mut current = false;
+        let mut n = 0u32;
+        for x in 0..w {
+            for y in 0..h {
+                let v = mask.get_pixel(x, y).0[0] > 0;
+                if v != current {
+                    counts.push(n);
+                    current = v;
+                    n = 0;
+                }
+                n += 1;
+            }
+        }
+        counts.push(n);
+        Self {
+            size: [h, w],
+            counts,
+            ..Default::default()
+        }
+    }
+
+    /// Decode to a luma mask, foreground pixels are 255
+    pub fn to_luma(&self) -> GrayImage {
+        let [h, w] = self.size;
+        let mut mask = GrayImage::new(w, h);
+        let mut i = 0u32;
+        for (k, &n) in self.counts.iter().enumerate() {
+            if k % 2 == 1 {
+                for j in i..i + n {
+                    mask.put_pixel(j / h, j % h, image::Luma([255u8]));
+                }
+            }
+            i += n;
+        }
+        mask
+    }
+
+    /// Compressed counts string of COCO (pycocotools' `rleToString`)
+    pub fn to_coco_string(&self) -> String {
+        let mut s = String::new();
+        for i in 0..self.counts.len() {
+            let mut x = self.counts[i] as i64;
+            if i > 2 {
+                x -= self.counts[i - 2] as i64;
+            }
+            loop {
+                let mut c = (x & 0x1f) as u8;
+                x >>= 5;
+                let more = if c & 0x10 != 0 { x != -1 } else { x != 0 };
+                if more {
+                    c |= 0x20;
+                }
+                s.push((c + 48) as char);
+                if !more {
+                    break;
+                }
+            }
+        }
+        s
+    }
+
+    /// Decode a COCO compressed counts string of a (height, width) mask
+    pub fn from_coco_string(size: [u32; 2], s: &str) -> anyhow::Result<Self> {
+        let mut counts: Vec<u32> = Vec::new();
+        let mut bytes = s.bytes();
+        while let Some(mut b) = bytes.next() {
+            let (mut x, mut k) = (0i64, 0);
+            loop {
+                let c = b.wrapping_sub(48) as i64;
+                if !(0..64).contains(&c) || k > 12 {
+                    anyhow::bail!("Invalid COCO RLE string: {s:?}");
+                }
+                x |= (c & 0x1f) << (5 * k);
+                k += 1;
+                if c & 0x20 == 0 {
+                    if c & 0x10 != 0 {
+                        x |= -1i64 << (5 * k);
+                    }
+                    break;
+                }
+                b = match bytes.next() {
+                    Some(b) => b,
+                    None => anyhow::bail!("Truncated COCO RLE string: {s:?}"),
+                };
+            }
+            if counts.len() > 2 {
+                x += counts[counts.len() - 2] as i64;
+            }
+            counts.push(
+                u32::try_from(x).map_err(|_| anyhow::anyhow!("Invalid COCO RLE string: {s:?}"))?,
+            );
+        }
+        if counts.iter().map(|&x| x as u64).sum::<u64>() != size[0] as u64 * size[1] as u64 {
+            anyhow::bail!(
+                "COCO RLE counts do not cover a {}x{} mask",
+                size[0],
+                size[1]
+            );
+        }
+        Ok(Self {
+            size,
+            counts,
+            ..Default::default()
+        })
+    }
+
+    pub fn with_id(mut self, x: isize) -> Self {
+        self.id = x;
+        self
+    }
+
+    pub fn with_name(mut self, x: Option<String>) -> Self {
+        self.name = x;
+        self
+    }
+
+    pub fn with_confidence(mut self, x: f32) -> Self {
+        self.confidence = x;
+        self
+    }
+
+    pub fn size(&self) -> [u32; 2] {
+        self.size
+    }
+
+    pub fn counts(&self) -> &Vec<u32> {
+        &self.counts
+    }
+
+    pub fn id(&self) -> isize {
+        self.id
+    }
+
+    pub fn name(&self) -> Option<&String> {
+        self.name.as_ref()
+    }
+
+    pub fn confidence(&self) -> f32 {
+        self.confidence
+    }
+
+    pub fn area(&self) -> u32 {
+        self.counts.iter().skip(1).step_by(2).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests_rle {
+    use super::Rle;
+    use image::{GrayImage, Luma};
+
+    #[test]
+    fn encode() {
+        // 3x2 (h x w), column-major: [0, 1, 1, 1, 0, 0]
+        let mut mask = GrayImage::new(2, 3);
+        mask.put_pixel(0, 1, Luma([255]));
+        mask.put_pixel(0, 2, Luma([255]));
+        mask.put_pixel(1, 0, Luma([1]));
+        let rle = Rle::from_luma(&mask);
+        assert_eq!(rle.size(), [3, 2]);
+        assert_eq!(rle.counts(), &vec![1, 3, 2]);
+        assert_eq!(rle.area(), 3);
+    }
+
+    #[test]
+    fn starts_with_foreground() {
+        let mut mask = GrayImage::new(2, 2);
+        mask.put_pixel(0, 0, Luma([255]));
+        let rle = Rle::from_luma(&mask);
+        assert_eq!(rle.counts(), &vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut mask = GrayImage::new(7, 5);
+        for (x, y) in [(0, 0), (3, 2), (3, 3), (6, 4), (4, 0)] {
+            mask.put_pixel(x, y, Luma([255]));
+        }
+        let rle = Rle::from_luma(&mask);
+        assert_eq!(rle.to_luma(), mask);
+        assert_eq!(Rle::from_luma(&GrayImage::new(4, 4)).counts(), &vec![16]);
+    }
+
+    #[test]
+    fn coco_string() {
+        let rle = |counts: Vec<u32>| Rle {
+            size: [1, counts.iter().sum()],
+            counts,
+            ..Default::default()
+        };
+        // deltas from the 2nd previous count from the 4th on, negative ones included
+        for (counts, s) in [
+            (vec![1, 3, 2], "132"),
+            (vec![1, 5, 1, 2], "151M"),
+            (vec![100], "T3"),
+        ] {
+            let x = rle(counts);
+            assert_eq!(x.to_coco_string(), s);
+            assert_eq!(Rle::from_coco_string(x.size(), s).unwrap(), x);
+        }
+        assert!(Rle::from_coco_string([1, 7], "132").is_err());
+        assert!(Rle::from_coco_string([1, 1], "T").is_err());
+    }
+}
@@ -1,6 +1,13 @@
-use crate::{Bbox, Keypoint, Mask, Mbr, Polygon, Prob};
+use geo::Translate;
+use image::{DynamicImage, GrayImage};
+use ndarray::Array2;
+
+use anyhow::Result;
+
+use crate::{Bbox, Embedding, Keypoint, Mask, Mbr, NmsMethod, Polygon, Prob, Rle};
 
 #[derive(Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Y {
     probs: Option<Prob>,
     bboxes: Option<Vec<Bbox>>,
@@ -9,6 +16,9 @@ pub struct Y {
     polygons: Option<Vec<Polygon>>,
     texts: Option<Vec<String>>,
     masks: Option<Vec<Mask>>,
+    rles: Option<Vec<Rle>>,
+    embeddings: Option<Vec<Embedding>>, // one per bbox
+    scores: Option<Vec<Vec<f32>>>,      // all classes' scores, one per bbox
 }
 
 impl std::fmt::Debug for Y {
@@ -47,6 +57,21 @@ impl std::fmt::Debug for Y {
                 f.field("Masks", &x);
             }
         }
+        if let Some(x) = &self.rles {
+            if !x.is_empty() {
+                f.field("Rles", &x);
+            }
+        }
+        if let Some(x) = &self.embeddings {
+            if !x.is_empty() {
+                f.field("Embeddings", &x);
+            }
+        }
+        if let Some(x) = &self.scores {
+            if !x.is_empty() {
+                f.field("Scores", &x.len());
+            }
+        }
         f.finish()
     }
 }
@@ -57,6 +82,11 @@ impl Y {
         self
     }
 
+    pub fn with_rles(mut self, rles: &[Rle]) -> Self {
+        self.rles = Some(rles.to_vec());
+        self
+    }
+
     pub fn with_probs(mut self, probs: Prob) -> Self {
         self.probs = Some(probs);
         self
@@ -86,10 +116,24 @@ impl Y {
         self
     }
 
+    pub fn with_embeddings(mut self, embeddings: &[Embedding]) -> Self {
+        self.embeddings = Some(embeddings.to_vec());
+        self
+    }
+
+    pub fn with_scores(mut self, scores: &[Vec<f32>]) -> Self {
+        self.scores = Some(scores.to_vec());
+        self
+    }
+
     pub fn masks(&self) -> Option<&Vec<Mask>> {
         self.masks.as_ref()
     }
 
+    pub fn rles(&self) -> Option<&Vec<Rle>> {
+        self.rles.as_ref()
+    }
+
     pub fn probs(&self) -> Option<&Prob> {
         self.probs.as_ref()
     }
@@ -114,60 +158,587 @@ impl Y {
         self.texts.as_ref()
     }
 
-    pub fn apply_bboxes_nms(mut self, iou_threshold: f32) -> Self {
-        match &mut self.bboxes {
-            None => self,
-            Some(ref mut bboxes) => {
-                Self::nms_bboxes(bboxes, iou_threshold);
-                self
+    pub fn embeddings(&self) -> Option<&Vec<Embedding>> {
+        self.embeddings.as_ref()
+    }
+
+    pub fn scores(&self) -> Option<&Vec<Vec<f32>>> {
+        self.scores.as_ref()
+    }
+
+    /// Attach an appearance embedding to each bbox by running `encoder` once on all crops of `x`.
+    /// `encoder` returns (n, ndim), e.g. `Clip::encode_images`.
+    pub fn embed_bboxes<F>(mut self, x: &DynamicImage, mut encoder: F) -> Result<Self>
+    where
+        F: FnMut(&[DynamicImage]) -> Result<Embedding>,
+    {
+        let bboxes = match &self.bboxes {
+            Some(bboxes) if !bboxes.is_empty() => bboxes,
+            _ => return Ok(self),
+        };
+        let (w, h) = (x.width() as f32, x.height() as f32);
+        let crops = bboxes
+            .iter()
+            .map(|bbox| {
+                let x1 = bbox.xmin().max(0.).min(w - 1.).floor();
+                let y1 = bbox.ymin().max(0.).min(h - 1.).floor();
+                let x2 = bbox.xmax().max(x1 + 1.).min(w).ceil();
+                let y2 = bbox.ymax().max(y1 + 1.).min(h).ceil();
+                x.crop_imm(x1 as u32, y1 as u32, (x2 - x1) as u32, (y2 - y1) as u32)
+            })
+            .collect::<Vec<_>>();
+        let embeddings = encoder(&crops)?.rows();
+        if embeddings.len() != crops.len() {
+            anyhow::bail!(
+                "Encoder returned {} embeddings for {} crops",
+                embeddings.len(),
+                crops.len()
+            );
+        }
+        self.embeddings = Some(embeddings);
+        Ok(self)
+    }
+
+    /// Top-1 class (name, or id if no names) & score of classification results
+    pub fn top_class(&self) -> Option<(String, f32)> {
+        let (id, confidence, name) = self.probs.as_ref()?.topk(1).into_iter().next()?;
+        Some((name.unwrap_or(id.to_string()), confidence))
+    }
+
+    /// Number of detections (bboxes & mbrs) per class (name, or id if no names), most frequent first
+    pub fn class_histogram(&self) -> Vec<(String, usize)> {
+        let mut counts: std::collections::HashMap<String, usize> = Default::default();
+        let classes = self
+            .bboxes
+            .iter()
+            .flatten()
+            .map(|x| (x.id(), x.name()))
+            .chain(self.mbrs.iter().flatten().map(|x| (x.id(), x.name())));
+        for (id, name) in classes {
+            let class = name.cloned().unwrap_or(id.to_string());
+            *counts.entry(class).or_default() += 1;
+        }
+        let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// Dense per-class counts of detections (bboxes & mbrs) indexed by class id, zeros for
+    /// undetected classes. Ids outside `0..nc` are ignored.
+    pub fn class_histogram_dense(&self, nc: usize) -> Vec<usize> {
+        let mut counts = vec![0; nc];
+        let ids = self
+            .bboxes
+            .iter()
+            .flatten()
+            .map(|x| x.id())
+            .chain(self.mbrs.iter().flatten().map(|x| x.id()));
+        for id in ids {
+            if id >= 0 && (id as usize) < nc {
+                counts[id as usize] += 1;
+            }
+        }
+        counts
+    }
+
+    /// YOLO txt labels normalized by the image size, one row per instance:
+    /// `class cx cy w h [kx ky kconf ...]` for bboxes (with keypoints if any),
+    /// `class x1 y1 x2 y2 ...` for polygons, `class x1 y1 ... x4 y4` for mbrs
+    pub fn to_yolo_txt(&self, img_w: u32, img_h: u32) -> String {
+        self.yolo_txt(img_w, img_h, false)
+    }
+
+    /// Same as `to_yolo_txt` with the confidence appended to each row
+    pub fn to_yolo_txt_with_conf(&self, img_w: u32, img_h: u32) -> String {
+        self.yolo_txt(img_w, img_h, true)
+    }
+
+    fn yolo_txt(&self, img_w: u32, img_h: u32, with_conf: bool) -> String {
+        let (w, h) = (img_w.max(1) as f32, img_h.max(1) as f32);
+        let mut rows = Vec::new();
+        let mut row = |id: isize, xs: Vec<f32>, conf: f32| {
+            let mut row = id.to_string();
+            xs.iter().for_each(|x| row.push_str(&format!(" {x:.6}")));
+            if with_conf {
+                row.push_str(&format!(" {conf:.6}"));
+            }
+            rows.push(row);
+        };
+
+        // segments replace their bboxes, as in YOLO seg labels
+        match &self.polygons {
+            Some(polygons) if !polygons.is_empty() => {
+                for x in polygons.iter() {
+                    let coords = x.polygon().exterior().coords().collect::<Vec<_>>();
+                    let n = match coords.as_slice() {
+                        [first, .., last] if first == last => coords.len() - 1,
+                        _ => coords.len(),
+                    };
+                    let xs = coords[..n]
+                        .iter()
+                        .flat_map(|c| [c.x as f32 / w, c.y as f32 / h])
+                        .collect();
+                    row(x.id(), xs, x.confidence());
+                }
+            }
+            _ => {
+                for (i, x) in self.bboxes.iter().flatten().enumerate() {
+                    let mut xs = vec![x.cx() / w, x.cy() / h, x.width() / w, x.height() / h];
+                    if let Some(kpts) = self.keypoints.as_ref().and_then(|k| k.get(i)) {
+                        xs.extend(
+                            kpts.iter()
+                                .flat_map(|k| [k.x() / w, k.y() / h, k.confidence()]),
+                        );
+                    }
+                    row(x.id(), xs, x.confidence());
+                }
             }
         }
+        for x in self.mbrs.iter().flatten() {
+            let xs = x
+                .vertices()
+                .iter()
+                .take(4)
+                .flat_map(|c| [c.x as f32 / w, c.y as f32 / h])
+                .collect();
+            row(x.id(), xs, x.confidence());
+        }
+        rows.iter().map(|x| format!("{x}\n")).collect()
+    }
+
+    /// Top `k` (id, probability, name) of classification probs, empty if none
+    pub fn topk(&self, k: usize) -> Vec<(usize, f32, Option<String>)> {
+        self.probs.as_ref().map(|x| x.topk(k)).unwrap_or_default()
+    }
+
+    /// COCO RLEs of masks, non-zero pixels are foreground, carrying their id, name & confidence
+    pub fn masks_to_rle(&self) -> Vec<Rle> {
+        self.masks
+            .iter()
+            .flatten()
+            .map(|x| {
+                Rle::from_luma(&x.mask().to_luma8())
+                    .with_id(x.id())
+                    .with_name(x.name().cloned())
+                    .with_confidence(x.confidence())
+            })
+            .collect()
     }
 
-    pub fn apply_mbrs_nms(mut self, iou_threshold: f32) -> Self {
-        match &mut self.mbrs {
-            None => self,
-            Some(ref mut mbrs) => {
-                mbrs.sort_by(|b1, b2| {
-                    b2.confidence()
-                        .partial_cmp(&b1.confidence())
-                        .unwrap_or(std::cmp::Ordering::Equal)
+    /// Vector polygons traced from masks, with holes, one per connected region, carrying
+    /// the mask's id, name & confidence. Simplified by Douglas-Peucker if `epsilon`.
+    pub fn mask_polygons(&self, epsilon: Option<f64>) -> Vec<Polygon> {
+        let mut ys = Vec::new();
+        for mask in self.masks.iter().flatten() {
+            let contours =
+                imageproc::contours::find_contours_with_threshold(&mask.mask().to_luma8(), 0);
+            for x in Polygon::from_contours(&contours) {
+                let polygon = Polygon::default()
+                    .with_polygon(x)
+                    .with_id(mask.id())
+                    .with_name(mask.name().cloned())
+                    .with_confidence(mask.confidence());
+                ys.push(match epsilon {
+                    Some(eps) => polygon.simplify(eps),
+                    None => polygon,
                 });
-                let mut current_index = 0;
-                for index in 0..mbrs.len() {
-                    let mut drop = false;
-                    for prev_index in 0..current_index {
-                        let iou = mbrs[prev_index].iou(&mbrs[index]);
-                        if iou > iou_threshold {
-                            drop = true;
-                            break;
+            }
+        }
+        ys
+    }
+
+    /// Bboxes in the COCO results format, see `export::coco`
+    pub fn to_coco(
+        &self,
+        image_id: u64,
+        map: crate::export::CategoryMap,
+    ) -> anyhow::Result<Vec<crate::export::CocoDetection>> {
+        self.bboxes
+            .iter()
+            .flatten()
+            .map(|x| crate::export::CocoDetection::from_bbox(image_id, x, map))
+            .collect()
+    }
+
+    /// Class-index map of (height, width) from masks & RLEs, 255 for background.
+    /// Instances are drawn in ascending confidence so the most confident wins; ids >= 255 are skipped.
+    pub fn to_semantic_map(&self, width: u32, height: u32) -> Array2<u8> {
+        let mut map = Array2::from_elem((height as usize, width as usize), 255u8);
+        let mut instances = self
+            .masks
+            .iter()
+            .flatten()
+            .map(|x| (x.id(), x.confidence(), x.mask().to_luma8()))
+            .chain(
+                self.rles
+                    .iter()
+                    .flatten()
+                    .map(|x| (x.id(), x.confidence(), x.to_luma())),
+            )
+            .filter(|(id, _, _)| (0..255).contains(id))
+            .collect::<Vec<_>>();
+        instances.sort_by(|a, b| a.1.total_cmp(&b.1));
+        for (id, _, mask) in instances.iter() {
+            for (x, y, p) in mask.enumerate_pixels() {
+                if p.0[0] > 0 && x < width && y < height {
+                    map[[y as usize, x as usize]] = *id as u8;
+                }
+            }
+        }
+        map
+    }
+
+    /// Human-readable table: idx, class, conf, xyxy
+    pub fn to_table(&self) -> String {
+        let mut rows: Vec<[String; 4]> = Vec::new();
+        let class = |id: isize, name: Option<&String>| match name {
+            Some(name) => name.to_owned(),
+            None => id.to_string(),
+        };
+        let xyxy =
+            |x1: f32, y1: f32, x2: f32, y2: f32| format!("{x1:.1}, {y1:.1}, {x2:.1}, {y2:.1}");
+        if let Some(probs) = &self.probs {
+            for (id, confidence, name) in probs.labels().unwrap_or_else(|| probs.topk(5)) {
+                rows.push([
+                    rows.len().to_string(),
+                    class(id as isize, name.as_ref()),
+                    format!("{confidence:.3}"),
+                    "-".to_string(),
+                ]);
+            }
+        }
+        for x in self.bboxes.iter().flatten() {
+            rows.push([
+                rows.len().to_string(),
+                class(x.id(), x.name()),
+                format!("{:.3}", x.confidence()),
+                xyxy(x.xmin(), x.ymin(), x.xmax(), x.ymax()),
+            ]);
+        }
+        for x in self.mbrs.iter().flatten() {
+            rows.push([
+                rows.len().to_string(),
+                class(x.id(), x.name()),
+                format!("{:.3}", x.confidence()),
+                xyxy(x.xmin(), x.ymin(), x.xmax(), x.ymax()),
+            ]);
+        }
+
+        let header = ["idx", "class", "conf", "xyxy"].map(String::from);
+        let mut widths = header.clone().map(|x| x.len());
+        for row in rows.iter() {
+            for (w, x) in widths.iter_mut().zip(row.iter()) {
+                *w = (*w).max(x.chars().count());
+            }
+        }
+        let line = |row: &[String; 4]| {
+            row.iter()
+                .zip(widths.iter())
+                .map(|(x, w)| format!("{x:<w$}"))
+                .collect::<Vec<_>>()
+                .join(" | ")
+                .trim_end()
+                .to_string()
+        };
+        let mut table = vec![
+            line(&header),
+            widths
+                .iter()
+                .map(|w| "-".repeat(*w))
+                .collect::<Vec<_>>()
+                .join("-+-"),
+        ];
+        table.extend(rows.iter().map(line));
+        table.join("\n")
+    }
+
+    /// Move results of a crop at (dx, dy) into a frame of (width, height)
+    pub fn with_offset(mut self, dx: u32, dy: u32, width: u32, height: u32) -> Self {
+        let (dx_, dy_) = (dx as f32, dy as f32);
+        let paste = |x: &GrayImage| {
+            let mut canvas = GrayImage::new(width, height);
+            image::imageops::replace(&mut canvas, x, dx as i64, dy as i64);
+            canvas
+        };
+        if let Some(xs) = &mut self.bboxes {
+            for x in xs.iter_mut() {
+                *x = x
+                    .clone()
+                    .with_xywh(x.xmin() + dx_, x.ymin() + dy_, x.width(), x.height());
+            }
+        }
+        if let Some(xss) = &mut self.keypoints {
+            for x in xss.iter_mut().flatten().filter(|x| x.visible()) {
+                *x = x.clone().with_xy(x.x() + dx_, x.y() + dy_);
+            }
+        }
+        if let Some(xs) = &mut self.mbrs {
+            for x in xs.iter_mut() {
+                let ls = x
+                    .vertices()
+                    .into_iter()
+                    .collect::<geo::LineString>()
+                    .translate(dx as f64, dy as f64);
+                *x = Mbr::from_line_string(ls)
+                    .with_id(x.id())
+                    .with_confidence(x.confidence())
+                    .with_name(x.name().cloned());
+            }
+        }
+        if let Some(xs) = &mut self.polygons {
+            for x in xs.iter_mut() {
+                let polygon = x.polygon().translate(dx as f64, dy as f64);
+                *x = x.clone().with_polygon(polygon);
+            }
+        }
+        if let Some(xs) = &mut self.masks {
+            for x in xs.iter_mut() {
+                let mask = DynamicImage::from(paste(&x.mask().to_luma8()));
+                *x = x.clone().with_mask(mask);
+            }
+        }
+        if let Some(xs) = &mut self.rles {
+            for x in xs.iter_mut() {
+                *x = Rle::from_luma(&paste(&x.to_luma()))
+                    .with_id(x.id())
+                    .with_name(x.name().cloned())
+                    .with_confidence(x.confidence());
+            }
+        }
+        self
+    }
+
+    /// Replace each bbox with the tight box of its visible keypoints, padded by `pad` pixels
+    pub fn refine_boxes_from_keypoints(mut self, pad: f32) -> Self {
+        const MIN_VISIBLE: usize = 3;
+        if let (Some(bboxes), Some(keypoints)) = (&mut self.bboxes, &self.keypoints) {
+            for (bbox, kpts) in bboxes.iter_mut().zip(keypoints.iter()) {
+                let visible = kpts.iter().filter(|x| x.visible()).collect::<Vec<_>>();
+                if visible.len() < MIN_VISIBLE {
+                    continue;
+                }
+                let (mut x1, mut y1, mut x2, mut y2) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+                for kpt in visible.iter() {
+                    x1 = x1.min(kpt.x());
+                    y1 = y1.min(kpt.y());
+                    x2 = x2.max(kpt.x());
+                    y2 = y2.max(kpt.y());
+                }
+                *bbox = bbox.clone().with_xyxy(
+                    (x1 - pad).max(0.),
+                    (y1 - pad).max(0.),
+                    x2 + pad,
+                    y2 + pad,
+                );
+            }
+        }
+        self
+    }
+
+    pub fn apply_bboxes_nms(self, iou_threshold: f32) -> Self {
+        self.apply_bboxes_nms_exempt(iou_threshold, &[], false, true)
+    }
+
+    /// NMS where boxes of `exempt` class ids are always kept. With `exempt_suppress`,
+    /// kept exempt boxes still suppress overlapping boxes of other classes.
+    /// Without `class_agnostic`, boxes only suppress boxes of the same class.
+    pub fn apply_bboxes_nms_exempt(
+        mut self,
+        iou_threshold: f32,
+        exempt: &[usize],
+        exempt_suppress: bool,
+        class_agnostic: bool,
+    ) -> Self {
+        if let Some(ref mut bboxes) = self.bboxes {
+            Self::nms_bboxes_exempt(
+                bboxes,
+                iou_threshold,
+                exempt,
+                exempt_suppress,
+                class_agnostic,
+            );
+        }
+        self
+    }
+
+    /// Soft-NMS: bboxes overlapping a kept one get their confidence decayed by `method`
+    /// instead of being removed, same `exempt` & `class_agnostic` rules as the hard one.
+    /// Re-threshold afterwards, e.g. with `apply_min_conf`.
+    pub fn apply_bboxes_soft_nms(
+        mut self,
+        iou_threshold: f32,
+        method: NmsMethod,
+        exempt: &[usize],
+        exempt_suppress: bool,
+        class_agnostic: bool,
+    ) -> Self {
+        if let Some(ref mut bboxes) = self.bboxes {
+            let is_exempt = |id: isize| id >= 0 && exempt.contains(&(id as usize));
+            let mut rest = std::mem::take(bboxes);
+            while !rest.is_empty() {
+                let (i, _) = rest
+                    .iter()
+                    .enumerate()
+                    .max_by(|a, b| a.1.confidence().total_cmp(&b.1.confidence()))
+                    .unwrap();
+                let best = rest.swap_remove(i);
+                if exempt_suppress || !is_exempt(best.id()) {
+                    for x in rest.iter_mut() {
+                        if is_exempt(x.id()) || (!class_agnostic && x.id() != best.id()) {
+                            continue;
                         }
+                        let decay = method.decay(best.iou(x), iou_threshold);
+                        *x = x.clone().with_confidence(x.confidence() * decay);
                     }
-                    if !drop {
-                        mbrs.swap(current_index, index);
-                        current_index += 1;
+                    rest.retain(|x| x.confidence() > 0.);
+                }
+                bboxes.push(best);
+            }
+        }
+        self
+    }
+
+    /// Keep bboxes (with their keypoints, embeddings & scores, if aligned) and mbrs scoring at least `conf`
+    pub fn apply_min_conf(mut self, conf: f32) -> Self {
+        if let Some(bboxes) = self.bboxes.take() {
+            let keep = bboxes
+                .iter()
+                .map(|x| x.confidence() >= conf)
+                .collect::<Vec<_>>();
+            self.keypoints = self.keypoints.take().map(|x| Self::keep_aligned(x, &keep));
+            self.embeddings = self.embeddings.take().map(|x| Self::keep_aligned(x, &keep));
+            self.scores = self.scores.take().map(|x| Self::keep_aligned(x, &keep));
+            self.bboxes = Some(Self::keep_aligned(bboxes, &keep));
+        }
+        if let Some(mbrs) = &mut self.mbrs {
+            mbrs.retain(|x| x.confidence() >= conf);
+        }
+        self
+    }
+
+    /// Items whose `keep` is true, untouched if not aligned
+    fn keep_aligned<T>(xs: Vec<T>, keep: &[bool]) -> Vec<T> {
+        if xs.len() != keep.len() {
+            return xs;
+        }
+        xs.into_iter()
+            .zip(keep.iter())
+            .filter_map(|(x, &k)| k.then_some(x))
+            .collect()
+    }
+
+    /// Remove near-duplicates: overlapping a kept box by more than `iou_threshold`
+    /// with a confidence at most `conf_delta` lower
+    pub fn apply_bboxes_dedup(mut self, iou_threshold: f32, conf_delta: f32) -> Self {
+        if let Some(ref mut bboxes) = self.bboxes {
+            bboxes.sort_by(|b1, b2| b2.confidence().total_cmp(&b1.confidence()));
+            let mut kept: Vec<Bbox> = Vec::with_capacity(bboxes.len());
+            for bbox in bboxes.drain(..) {
+                if !kept.iter().any(|x| {
+                    x.confidence() - bbox.confidence() <= conf_delta && x.iou(&bbox) > iou_threshold
+                }) {
+                    kept.push(bbox);
+                }
+            }
+            *bboxes = kept;
+        }
+        self
+    }
+
+    pub fn apply_mbrs_nms(self, iou_threshold: f32) -> Self {
+        self.apply_mbrs_nms_exempt(iou_threshold, &[], false, true)
+    }
+
+    pub fn apply_mbrs_nms_exempt(
+        mut self,
+        iou_threshold: f32,
+        exempt: &[usize],
+        exempt_suppress: bool,
+        class_agnostic: bool,
+    ) -> Self {
+        if let Some(ref mut mbrs) = self.mbrs {
+            Self::nms_mbrs_exempt(mbrs, iou_threshold, exempt, exempt_suppress, class_agnostic);
+        }
+        self
+    }
+
+    /// NMS of oriented boxes with IoU of the rotated rectangles (polygon intersection)
+    pub fn nms_mbrs(mbrs: &mut Vec<Mbr>, iou_threshold: f32) {
+        Self::nms_mbrs_exempt(mbrs, iou_threshold, &[], false, true)
+    }
+
+    pub fn nms_mbrs_exempt(
+        mbrs: &mut Vec<Mbr>,
+        iou_threshold: f32,
+        exempt: &[usize],
+        exempt_suppress: bool,
+        class_agnostic: bool,
+    ) {
+        mbrs.sort_by(|b1, b2| {
+            b2.confidence()
+                .partial_cmp(&b1.confidence())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let is_exempt = |id: isize| id >= 0 && exempt.contains(&(id as usize));
+        let mut current_index = 0;
+        for index in 0..mbrs.len() {
+            let mut drop = false;
+            if !is_exempt(mbrs[index].id()) {
+                for prev_index in 0..current_index {
+                    if !exempt_suppress && is_exempt(mbrs[prev_index].id()) {
+                        continue;
+                    }
+                    if !class_agnostic && mbrs[prev_index].id() != mbrs[index].id() {
+                        continue;
+                    }
+                    let iou = mbrs[prev_index].iou(&mbrs[index]);
+                    if iou > iou_threshold {
+                        drop = true;
+                        break;
                     }
                 }
-                mbrs.truncate(current_index);
-                self
+            }
+            if !drop {
+                mbrs.swap(current_index, index);
+                current_index += 1;
             }
         }
+        mbrs.truncate(current_index);
     }
 
     pub fn nms_bboxes(bboxes: &mut Vec<Bbox>, iou_threshold: f32) {
+        Self::nms_bboxes_exempt(bboxes, iou_threshold, &[], false, true)
+    }
+
+    pub fn nms_bboxes_exempt(
+        bboxes: &mut Vec<Bbox>,
+        iou_threshold: f32,
+        exempt: &[usize],
+        exempt_suppress: bool,
+        class_agnostic: bool,
+    ) {
         bboxes.sort_by(|b1, b2| {
             b2.confidence()
                 .partial_cmp(&b1.confidence())
                 .unwrap_or(std::cmp::Ordering::Equal)
         });
+        let is_exempt = |id: isize| id >= 0 && exempt.contains(&(id as usize));
         let mut current_index = 0;
         for index in 0..bboxes.len() {
             let mut drop = false;
-            for prev_index in 0..current_index {
-                let iou = bboxes[prev_index].iou(&bboxes[index]);
-                if iou > iou_threshold {
-                    drop = true;
-                    break;
+            if !is_exempt(bboxes[index].id()) {
+                for prev_index in 0..current_index {
+                    if !exempt_suppress && is_exempt(bboxes[prev_index].id()) {
+                        continue;
+                    }
+                    if !class_agnostic && bboxes[prev_index].id() != bboxes[index].id() {
+                        continue;
+                    }
+                    let iou = bboxes[prev_index].iou(&bboxes[index]);
+                    if iou > iou_threshold {
+                        drop = true;
+                        break;
+                    }
                 }
             }
             if !drop {
@@ -178,3 +749,426 @@ impl Y {
         bboxes.truncate(current_index);
     }
 }
+
+#[cfg(test)]
+mod tests_y {
+    use super::Y;
+    use crate::{Bbox, Embedding, Keypoint, Mask, Mbr, NmsMethod};
+    use image::{DynamicImage, GrayImage, Luma};
+
+    #[test]
+    fn to_table() {
+        let y = Y::default().with_bboxes(&[
+            Bbox::default()
+                .with_xyxy(1., 2., 30., 40.)
+                .with_id(0)
+                .with_confidence(0.9)
+                .with_name(Some("person".to_string())),
+            Bbox::default()
+                .with_xyxy(0., 0., 5., 5.)
+                .with_id(3)
+                .with_confidence(0.25),
+        ]);
+        let table = y.to_table();
+        let lines = table.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "idx | class  | conf  | xyxy");
+        assert_eq!(lines[2], "0   | person | 0.900 | 1.0, 2.0, 30.0, 40.0");
+        assert_eq!(lines[3], "1   | 3      | 0.250 | 0.0, 0.0, 5.0, 5.0");
+    }
+
+    #[test]
+    fn to_semantic_map() {
+        let square = |x0: u32, x1: u32| {
+            DynamicImage::from(GrayImage::from_fn(4, 3, |x, _| {
+                Luma([if (x0..x1).contains(&x) { 255 } else { 0 }])
+            }))
+        };
+        let y = Y::default().with_masks(&[
+            Mask::default()
+                .with_mask(square(0, 3))
+                .with_id(1)
+                .with_confidence(0.9),
+            Mask::default()
+                .with_mask(square(2, 4))
+                .with_id(7)
+                .with_confidence(0.5),
+            Mask::default()
+                .with_mask(square(0, 4))
+                .with_id(-1)
+                .with_confidence(1.),
+        ]);
+        let map = y.to_semantic_map(4, 3);
+        assert_eq!(map.dim(), (3, 4));
+        assert_eq!(map.row(0).to_vec(), vec![1, 1, 1, 7]);
+        assert_eq!(
+            Y::default().to_semantic_map(2, 1).row(0).to_vec(),
+            vec![255, 255]
+        );
+    }
+
+    #[test]
+    fn class_histogram_dense() {
+        let bbox = |id: isize| Bbox::default().with_id(id);
+        let y = Y::default().with_bboxes(&[bbox(2), bbox(0), bbox(2), bbox(-1), bbox(7)]);
+        assert_eq!(y.class_histogram_dense(4), vec![1, 0, 2, 0]);
+        assert_eq!(Y::default().class_histogram_dense(3), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn embed_bboxes() {
+        let mut img = image::RgbImage::new(20, 10);
+        for (x, _, p) in img.enumerate_pixels_mut() {
+            *p = if x < 10 {
+                image::Rgb([255, 0, 0])
+            } else {
+                image::Rgb([0, 0, 255])
+            };
+        }
+        let img = DynamicImage::from(img);
+        let encoder = |xs: &[DynamicImage]| -> anyhow::Result<Embedding> {
+            let v = xs
+                .iter()
+                .flat_map(|x| {
+                    let p = x.to_rgb8().get_pixel(0, 0).0;
+                    [p[0] as f32, p[1] as f32, p[2] as f32]
+                })
+                .collect::<Vec<_>>();
+            Ok(Embedding::new(
+                ndarray::Array::from_shape_vec((xs.len(), 3), v)?.into_dyn(),
+            ))
+        };
+        let y = Y::default()
+            .with_bboxes(&[
+                Bbox::default().with_xyxy(0., 0., 5., 5.),
+                Bbox::default().with_xyxy(12., 2., 30., 8.),
+                Bbox::default().with_xyxy(1., 5., 4., 9.),
+            ])
+            .embed_bboxes(&img, encoder)
+            .unwrap();
+        let es = y.embeddings().unwrap();
+        assert_eq!(es.len(), 3);
+        assert!((es[0].cosine(&es[2]).unwrap() - 1.).abs() < 1e-6);
+        assert!(es[0].cosine(&es[1]).unwrap().abs() < 1e-6);
+    }
+
+    #[test]
+    fn min_conf() {
+        let bbox = |conf: f32| Bbox::default().with_confidence(conf);
+        let kpts = |x: f32| vec![Keypoint::default().with_xy(x, x).with_confidence(1.)];
+        let y = Y::default()
+            .with_bboxes(&[bbox(0.9), bbox(0.2), bbox(0.5)])
+            .with_keypoints(&[kpts(1.), kpts(2.), kpts(3.)])
+            .apply_min_conf(0.5);
+        assert_eq!(y.bboxes().unwrap().len(), 2);
+        let xs = y
+            .keypoints()
+            .unwrap()
+            .iter()
+            .map(|x| x[0].x())
+            .collect::<Vec<_>>();
+        assert_eq!(xs, vec![1., 3.]);
+    }
+
+    #[test]
+    fn bboxes_dedup() {
+        let bbox = |x: f32, conf: f32| {
+            Bbox::default()
+                .with_xyxy(x, 0., x + 100., 100.)
+                .with_confidence(conf)
+        };
+        let y = Y::default()
+            .with_bboxes(&[
+                bbox(0., 0.8),
+                bbox(1., 0.81),
+                bbox(2., 0.5),
+                bbox(300., 0.8),
+            ])
+            .apply_bboxes_dedup(0.7, 0.05);
+        let confs = y
+            .bboxes()
+            .unwrap()
+            .iter()
+            .map(|x| x.confidence())
+            .collect::<Vec<_>>();
+        assert_eq!(confs, vec![0.81, 0.8, 0.5]);
+    }
+
+    #[test]
+    fn nms_mbrs_rotated() {
+        // two parallel ships at 45 degrees, side by side: axis-aligned boxes overlap heavily
+        let ship = |cx: f64, cy: f64, conf: f32| {
+            Mbr::from_cxcywhd(cx, cy, 100., 10., 45.).with_confidence(conf)
+        };
+        let mut mbrs = vec![
+            ship(50., 50., 0.9),
+            ship(58., 42., 0.8),
+            ship(51., 51., 0.7),
+        ];
+        let (a, b) = (&mbrs[0], &mbrs[1]);
+        let aabb = |x: &Mbr| Bbox::default().with_xyxy(x.xmin(), x.ymin(), x.xmax(), x.ymax());
+        assert!(aabb(a).iou(&aabb(b)) > 0.5);
+        assert!(a.iou(b) < 0.1);
+
+        Y::nms_mbrs(&mut mbrs, 0.5);
+        let confs = mbrs.iter().map(|x| x.confidence()).collect::<Vec<_>>();
+        assert_eq!(confs, vec![0.9, 0.8]);
+    }
+
+    #[test]
+    fn nms_exempt_classes() {
+        let bboxes = [
+            Bbox::default()
+                .with_xyxy(0., 0., 100., 100.)
+                .with_id(1)
+                .with_confidence(0.6),
+            Bbox::default()
+                .with_xyxy(0., 0., 100., 100.)
+                .with_id(0)
+                .with_confidence(0.9),
+            Bbox::default()
+                .with_xyxy(2., 2., 100., 100.)
+                .with_id(0)
+                .with_confidence(0.8),
+        ];
+        let y = Y::default().with_bboxes(&bboxes).apply_bboxes_nms(0.5);
+        assert_eq!(y.bboxes().unwrap().len(), 1);
+
+        // page box (id 1) is kept and does not suppress the others
+        let y = Y::default()
+            .with_bboxes(&bboxes)
+            .apply_bboxes_nms_exempt(0.5, &[1], false, true);
+        let ids = y
+            .bboxes()
+            .unwrap()
+            .iter()
+            .map(|b| b.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1]);
+
+        // exempt boxes ranked above others may suppress them
+        let y = Y::default()
+            .with_bboxes(&bboxes)
+            .apply_bboxes_nms_exempt(0.5, &[0], true, true);
+        let ids = y
+            .bboxes()
+            .unwrap()
+            .iter()
+            .map(|b| b.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 0]);
+
+        // per-class NMS keeps overlapping boxes of different classes
+        let y = Y::default()
+            .with_bboxes(&bboxes)
+            .apply_bboxes_nms_exempt(0.5, &[], false, false);
+        let ids = y
+            .bboxes()
+            .unwrap()
+            .iter()
+            .map(|b| b.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, vec![0, 1]);
+    }
+
+    #[test]
+    fn mask_polygons() {
+        // 6x6 square ring with a 2x2 hole, plus a separate dot
+        let mut luma = GrayImage::new(10, 10);
+        for (x, y) in (1..7).flat_map(|x| (1..7).map(move |y| (x, y))) {
+            if !(3..5).contains(&x) || !(3..5).contains(&y) {
+                luma.put_pixel(x, y, Luma([255]));
+            }
+        }
+        luma.put_pixel(8, 8, Luma([255]));
+        let y = Y::default().with_masks(&[Mask::default()
+            .with_mask(DynamicImage::from(luma))
+            .with_id(3)]);
+        let polygons = y.mask_polygons(None);
+        assert_eq!(polygons.len(), 2);
+        let ring = &polygons[0];
+        assert_eq!(ring.id(), 3);
+        assert!(ring.is_closed());
+        assert_eq!(ring.polygon().interiors().len(), 1);
+        assert!(polygons[1].polygon().interiors().is_empty());
+    }
+
+    #[test]
+    fn soft_nms() {
+        let bboxes = [
+            Bbox::default()
+                .with_xyxy(0., 0., 100., 100.)
+                .with_id(0)
+                .with_confidence(0.9),
+            Bbox::default()
+                .with_xyxy(0., 0., 100., 80.)
+                .with_id(0)
+                .with_confidence(0.8),
+            Bbox::default()
+                .with_xyxy(200., 200., 300., 300.)
+                .with_id(0)
+                .with_confidence(0.7),
+        ];
+        let confs = |y: Y| {
+            y.bboxes()
+                .unwrap()
+                .iter()
+                .map(|b| b.confidence())
+                .collect::<Vec<_>>()
+        };
+
+        // iou 0.8: linear keeps 0.8 * 0.2, the disjoint box is untouched
+        let y = Y::default().with_bboxes(&bboxes).apply_bboxes_soft_nms(
+            0.5,
+            NmsMethod::Linear,
+            &[],
+            false,
+            true,
+        );
+        let c = confs(y);
+        assert_eq!(c.len(), 3);
+        assert!((c[0] - 0.9).abs() < 1e-6 && (c[1] - 0.7).abs() < 1e-6);
+        assert!((c[2] - 0.16).abs() < 1e-5);
+
+        let y = Y::default().with_bboxes(&bboxes).apply_bboxes_soft_nms(
+            0.5,
+            NmsMethod::Gaussian { sigma: 0.5 },
+            &[],
+            false,
+            true,
+        );
+        assert!((confs(y)[2] - 0.8 * (-0.64f32 / 0.5).exp()).abs() < 1e-5);
+
+        // hard decay matches hard NMS
+        let y = Y::default().with_bboxes(&bboxes).apply_bboxes_soft_nms(
+            0.5,
+            NmsMethod::Hard,
+            &[],
+            false,
+            true,
+        );
+        assert_eq!(confs(y).len(), 2);
+    }
+
+    #[test]
+    fn refine_boxes_from_keypoints() {
+        let kpt = |x: f32, y: f32, c: f32| Keypoint::default().with_xy(x, y).with_confidence(c);
+        let bbox = Bbox::default().with_xyxy(0., 0., 100., 100.).with_id(0);
+        let y = Y::default()
+            .with_bboxes(&[bbox.clone(), bbox.clone()])
+            .with_keypoints(&[
+                vec![
+                    kpt(10., 20., 0.9),
+                    kpt(40., 30., 0.8),
+                    kpt(20., 60., 0.7),
+                    kpt(90., 90., 0.),
+                ],
+                vec![kpt(10., 20., 0.9), kpt(40., 30., 0.8), Keypoint::default()],
+            ])
+            .refine_boxes_from_keypoints(2.);
+        let bboxes = y.bboxes().unwrap();
+        assert_eq!(
+            bboxes[0],
+            Bbox::default().with_xyxy(8., 18., 42., 62.).with_id(0)
+        );
+        assert_eq!(bboxes[1], bbox);
+    }
+
+    #[test]
+    fn with_offset() {
+        let y = Y::default()
+            .with_bboxes(&[Bbox::default().with_xyxy(1., 2., 3., 4.).with_id(1)])
+            .with_keypoints(&[vec![
+                Keypoint::default().with_xy(5., 6.).with_confidence(0.9),
+                Keypoint::default(),
+            ]])
+            .with_offset(10, 20, 100, 100);
+        assert_eq!(
+            y.bboxes().unwrap()[0],
+            Bbox::default().with_xyxy(11., 22., 13., 24.).with_id(1)
+        );
+        let kpts = &y.keypoints().unwrap()[0];
+        assert_eq!((kpts[0].x(), kpts[0].y()), (15., 26.));
+        assert_eq!((kpts[1].x(), kpts[1].y()), (0., 0.));
+    }
+
+    #[test]
+    fn class_histogram() {
+        let bbox = |id: isize, name: &str| {
+            Bbox::default()
+                .with_id(id)
+                .with_name(Some(name.to_string()))
+        };
+        let y = Y::default().with_bboxes(&[
+            bbox(2, "car"),
+            bbox(0, "person"),
+            bbox(2, "car"),
+            bbox(1, "bike"),
+        ]);
+        assert_eq!(
+            y.class_histogram(),
+            vec![
+                ("car".to_string(), 2),
+                ("bike".to_string(), 1),
+                ("person".to_string(), 1)
+            ]
+        );
+        assert!(Y::default().class_histogram().is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let mut luma = GrayImage::new(3, 2);
+        luma.put_pixel(1, 1, Luma([255]));
+        let y = Y::default()
+            .with_bboxes(&[Bbox::default()
+                .with_xyxy(1., 2., 30., 40.)
+                .with_id(0)
+                .with_confidence(0.9)
+                .with_name(Some("person".to_string()))])
+            .with_keypoints(&[vec![Keypoint::default().with_xy(3., 4.).with_id(1)]])
+            .with_masks(&[Mask::default()
+                .with_mask(DynamicImage::from(luma.clone()))
+                .with_vec(luma.as_raw())
+                .with_id(0)])
+            .with_embeddings(&[Embedding::new(ndarray::Array::from_elem(
+                ndarray::IxDyn(&[1, 4]),
+                0.5,
+            ))]);
+        let s = serde_json::to_string(&y).unwrap();
+        assert!(s.contains("\"confidence\":0.9"));
+        assert_eq!(serde_json::from_str::<Y>(&s).unwrap(), y);
+    }
+
+    #[test]
+    fn yolo_txt() {
+        let y = Y::default()
+            .with_bboxes(&[Bbox::default()
+                .with_xyxy(10., 20., 30., 60.)
+                .with_id(3)
+                .with_confidence(0.5)])
+            .with_keypoints(&[vec![Keypoint::default()
+                .with_xy(50., 25.)
+                .with_confidence(0.9)]]);
+        assert_eq!(
+            y.to_yolo_txt(100, 50),
+            "3 0.200000 0.800000 0.200000 0.800000 0.500000 0.500000 0.900000\n"
+        );
+        assert!(y
+            .to_yolo_txt_with_conf(100, 50)
+            .ends_with(" 0.900000 0.500000\n"));
+
+        let y = Y::default().with_polygons(&[crate::Polygon::default()
+            .with_polygon(geo::Polygon::new(
+                vec![(0., 0.), (50., 0.), (50., 25.)].into(),
+                vec![],
+            ))
+            .with_id(1)]);
+        assert_eq!(
+            y.to_yolo_txt(100, 50),
+            "1 0.000000 0.000000 0.500000 0.000000 0.500000 0.500000\n"
+        );
+        assert!(Y::default().to_yolo_txt(10, 10).is_empty());
+    }
+}
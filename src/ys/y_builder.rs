@@ -0,0 +1,169 @@
+use anyhow::Result;
+
+use crate::{Bbox, Keypoint, Mbr, Polygon, Prob, Y};
+
+/// Build `Y` by hand, e.g. fixtures, keeping per-instance fields aligned with bboxes
+#[derive(Debug, Default)]
+pub struct YBuilder {
+    bboxes: Vec<Bbox>,
+    keypoints: Vec<Option<Vec<Keypoint>>>,
+    polygons: Vec<Option<Polygon>>,
+    mbrs: Vec<Mbr>,
+    probs: Option<Prob>,
+    texts: Vec<String>,
+    orphans: usize, // per-instance fields added before any bbox
+}
+
+impl YBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_bbox(mut self, x: Bbox) -> Self {
+        let x = x.with_id_born(self.bboxes.len() as isize);
+        self.bboxes.push(x);
+        self.keypoints.push(None);
+        self.polygons.push(None);
+        self
+    }
+
+    /// Shortcut of `add_bbox` from xyxy, class id & confidence
+    pub fn add_box(self, xyxy: [f32; 4], id: isize, confidence: f32) -> Self {
+        let [x1, y1, x2, y2] = xyxy;
+        self.add_bbox(
+            Bbox::default()
+                .with_xyxy(x1, y1, x2, y2)
+                .with_id(id)
+                .with_confidence(confidence),
+        )
+    }
+
+    /// Keypoints of the last added bbox
+    pub fn add_keypoints(mut self, x: &[Keypoint]) -> Self {
+        match self.keypoints.last_mut() {
+            Some(kpts) => *kpts = Some(x.to_vec()),
+            None => self.orphans += 1,
+        }
+        self
+    }
+
+    /// Polygon of the last added bbox
+    pub fn add_polygon(mut self, x: Polygon) -> Self {
+        match self.polygons.last_mut() {
+            Some(polygon) => *polygon = Some(x),
+            None => self.orphans += 1,
+        }
+        self
+    }
+
+    pub fn add_mbr(mut self, x: Mbr) -> Self {
+        self.mbrs.push(x);
+        self
+    }
+
+    pub fn add_text(mut self, x: &str) -> Self {
+        self.texts.push(x.to_string());
+        self
+    }
+
+    pub fn with_probs(mut self, x: Prob) -> Self {
+        self.probs = Some(x);
+        self
+    }
+
+    pub fn build(self) -> Result<Y> {
+        if self.orphans > 0 {
+            anyhow::bail!("{} keypoints/polygons added before any bbox", self.orphans);
+        }
+        let mut y = Y::default();
+        if !self.bboxes.is_empty() {
+            y = y.with_bboxes(&self.bboxes);
+        }
+
+        // keypoints: all or none, missing ones are filled with invisible keypoints
+        if self.keypoints.iter().any(|x| x.is_some()) {
+            let nk = self
+                .keypoints
+                .iter()
+                .flatten()
+                .map(|x| x.len())
+                .max()
+                .unwrap_or(0);
+            if self.keypoints.iter().flatten().any(|x| x.len() != nk) {
+                anyhow::bail!("All instances should have the same number of keypoints: {nk}");
+            }
+            let keypoints = self
+                .keypoints
+                .into_iter()
+                .map(|x| {
+                    x.unwrap_or_else(|| {
+                        (0..nk)
+                            .map(|i| Keypoint::default().with_id(i as isize))
+                            .collect()
+                    })
+                })
+                .collect::<Vec<_>>();
+            y = y.with_keypoints(&keypoints);
+        }
+
+        // polygons: all or none
+        if self.polygons.iter().any(|x| x.is_some()) {
+            let polygons = self.polygons.into_iter().collect::<Option<Vec<_>>>();
+            match polygons {
+                Some(polygons) => y = y.with_polygons(&polygons),
+                None => anyhow::bail!("Polygons should be added for all bboxes or none"),
+            }
+        }
+
+        if !self.mbrs.is_empty() {
+            y = y.with_mbrs(&self.mbrs);
+        }
+        if !self.texts.is_empty() {
+            y = y.with_texts(&self.texts);
+        }
+        if let Some(probs) = self.probs {
+            y = y.with_probs(probs);
+        }
+        Ok(y)
+    }
+}
+
+#[cfg(test)]
+mod tests_y_builder {
+    use super::YBuilder;
+    use crate::Keypoint;
+
+    #[test]
+    fn aligned() {
+        let kpts = vec![Keypoint::default().with_xy(1., 2.).with_confidence(0.9); 3];
+        let y = YBuilder::new()
+            .add_box([0., 0., 10., 10.], 0, 0.9)
+            .add_keypoints(&kpts)
+            .add_box([5., 5., 20., 20.], 1, 0.8)
+            .build()
+            .unwrap();
+        let (bboxes, keypoints) = (y.bboxes().unwrap(), y.keypoints().unwrap());
+        assert_eq!(bboxes.len(), 2);
+        assert_eq!(bboxes[1].id_born(), 1);
+        assert_eq!(keypoints[0], kpts);
+        assert_eq!(keypoints[1].len(), 3);
+        assert!(keypoints[1].iter().all(|x| !x.visible()));
+        assert_eq!(keypoints[1][2].id(), 2);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(YBuilder::new()
+            .add_keypoints(&[Keypoint::default()])
+            .build()
+            .is_err());
+        assert!(YBuilder::new()
+            .add_box([0., 0., 1., 1.], 0, 0.5)
+            .add_keypoints(&[Keypoint::default()])
+            .add_box([0., 0., 1., 1.], 0, 0.5)
+            .add_keypoints(&[Keypoint::default(), Keypoint::default()])
+            .build()
+            .is_err());
+        assert_eq!(YBuilder::new().build().unwrap(), crate::Y::default());
+    }
+}
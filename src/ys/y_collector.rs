@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::Y;
+
+/// Thread-safe collector of `Y`s keyed by the original image index
+#[derive(Debug, Default)]
+pub struct YCollector {
+    ys: Mutex<BTreeMap<usize, Y>>,
+}
+
+impl YCollector {
+    pub fn insert(&self, idx: usize, y: Y) {
+        self.ys.lock().unwrap().insert(idx, y);
+    }
+
+    pub fn extend(&self, start: usize, ys: Vec<Y>) {
+        let mut map = self.ys.lock().unwrap();
+        for (i, y) in ys.into_iter().enumerate() {
+            map.insert(start + i, y);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.ys.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Ordered by index, missing indices are filled with empty `Y`s
+    pub fn into_ordered(self) -> Vec<Y> {
+        let map = self.ys.into_inner().unwrap();
+        let n = map.keys().next_back().map_or(0, |x| x + 1);
+        let mut ys = vec![Y::default(); n];
+        for (idx, y) in map {
+            ys[idx] = y;
+        }
+        ys
+    }
+}
+
+#[cfg(test)]
+mod tests_y_collector {
+    use super::YCollector;
+    use crate::Y;
+
+    #[test]
+    fn ordered_from_threads() {
+        let collector = YCollector::default();
+        std::thread::scope(|s| {
+            for shard in (0..4).rev() {
+                let collector = &collector;
+                s.spawn(move || {
+                    let ys = (0..3)
+                        .map(|i| Y::default().with_texts(&[format!("{}", shard * 3 + i)]))
+                        .collect();
+                    collector.extend(shard * 3, ys);
+                });
+            }
+        });
+        assert_eq!(collector.len(), 12);
+        let ys = collector.into_ordered();
+        for (idx, y) in ys.iter().enumerate() {
+            assert_eq!(y.texts().unwrap()[0], idx.to_string());
+        }
+    }
+
+    #[test]
+    fn gaps() {
+        let collector = YCollector::default();
+        collector.insert(2, Y::default().with_texts(&["2".to_string()]));
+        let ys = collector.into_ordered();
+        assert_eq!(ys.len(), 3);
+        assert_eq!(ys[0], Y::default());
+        assert!(ys[2].texts().is_some());
+    }
+}